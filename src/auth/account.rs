@@ -0,0 +1,118 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use md5::{Digest, Md5};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::error::{LauncherError, Result};
+
+// a token is renewed a bit before its expiry, so it doesn't expire while being used
+const EXPIRY_MARGIN: u64 = 60;
+
+pub(crate) fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Token {
+    pub value: String,
+    // unix timestamp (in seconds)
+    pub expires_at: u64,
+}
+
+impl Token {
+    pub(crate) fn expiring_in(value: String, expires_in: u64) -> Self {
+        Self {
+            value,
+            expires_at: unix_timestamp() + expires_in,
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.value.is_empty() || unix_timestamp() + EXPIRY_MARGIN >= self.expires_at
+    }
+}
+
+// tokens of every step of the Microsoft authentication, refreshed by Authenticator::refresh
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MicrosoftAccount {
+    // minecraft profile of the account
+    pub uuid: String,
+    pub username: String,
+    pub microsoft_token: Token,
+    // doesn't expire while it's used at least every 90 days
+    pub refresh_token: String,
+    pub xbox_live_token: Token,
+    pub xsts_token: Token,
+    pub user_hash: String,
+    pub minecraft_token: Token,
+}
+
+// account playing without Microsoft login, it can't join the online-mode servers
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OfflineAccount {
+    pub uuid: String,
+    pub username: String,
+}
+
+impl OfflineAccount {
+    // the uuid is the one the game gives to the offline players, so the saves and servers find the same player
+    pub fn new(username: &str) -> Result<Self> {
+        check_username(username)?;
+        Ok(Self {
+            uuid: offline_uuid(username),
+            username: username.to_string(),
+        })
+    }
+}
+
+// the names the game accepts : 3 to 16 letters, digits or underscores
+fn check_username(username: &str) -> Result<()> {
+    let is_valid = (3..=16).contains(&username.len())
+        && username.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !is_valid {
+        return Err(LauncherError::Auth(format!(
+            "invalid username {:?}, it must have 3 to 16 letters, digits or underscores",
+            username
+        )));
+    }
+    Ok(())
+}
+
+// uuid of version 3 made from "OfflinePlayer:<name>", like UUID.nameUUIDFromBytes in java
+pub(crate) fn offline_uuid(username: &str) -> String {
+    let mut hash: [u8; 16] = Md5::digest(format!("OfflinePlayer:{}", username)).into();
+    hash[6] = (hash[6] & 0x0f) | 0x30;
+    hash[8] = (hash[8] & 0x3f) | 0x80;
+    hex::encode(hash)
+}
+
+// an account saved by the AccountManager
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Account {
+    Microsoft(MicrosoftAccount),
+    Offline(OfflineAccount),
+}
+
+impl Account {
+    // uuid of the minecraft profile, used to find the account in the AccountManager
+    pub fn uuid(&self) -> &str {
+        match self {
+            Account::Microsoft(account) => &account.uuid,
+            Account::Offline(account) => &account.uuid,
+        }
+    }
+
+    pub fn username(&self) -> &str {
+        match self {
+            Account::Microsoft(account) => &account.username,
+            Account::Offline(account) => &account.username,
+        }
+    }
+}
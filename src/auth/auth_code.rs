@@ -0,0 +1,205 @@
+use std::time::Duration;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use reqwest::Url;
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+
+use crate::auth::endpoints::Endpoints;
+use crate::auth::microsoft::SCOPE;
+use crate::error::{LauncherError, Result};
+
+// ---------------------------------------------------------------------------------------- //
+// Authorization code flow with PKCE : the user logs in the browser, which is redirected to //
+// a http listener of the launcher on 127.0.0.1 with the code                               //
+// ---------------------------------------------------------------------------------------- //
+
+const SUCCESS_PAGE: &str = "<html><body>You are logged in, you can close this page and go back to the launcher.</body></html>";
+// time given to a connection to send its request, the idle ones are closed after it
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const ERROR_PAGE: &str = "<html><body>The login failed, go back to the launcher to try again.</body></html>";
+
+pub(crate) type OnUrl = Box<dyn FnOnce(&str) + Send>;
+
+// how the login page is opened, and when the login is given up
+// the default one prints the url of the page on stdout, and waits until the user logs in
+pub struct AuthCodeLogin {
+    on_url: OnUrl,
+    cancel_token: CancellationToken,
+    timeout: Option<Duration>,
+    // port of the listener on 127.0.0.1, 0 to let the os choose a free one
+    port: u16,
+}
+
+impl Default for AuthCodeLogin {
+    fn default() -> Self {
+        Self::new(|url| println!("Open this link in your browser to log in: {}\nWaiting authentication...", url))
+    }
+}
+
+impl AuthCodeLogin {
+    // on_url is called with the url of the login page, to open it in the browser of the user
+    pub fn new<F: FnOnce(&str) + Send + 'static>(on_url: F) -> Self {
+        Self {
+            on_url: Box::new(on_url),
+            cancel_token: CancellationToken::new(),
+            timeout: None,
+            port: 0,
+        }
+    }
+
+    // cancel the token (when the user closes the dialog for example) to stop the login with LoginCancelled
+    pub fn cancel_token(&self) -> CancellationToken {
+        self.cancel_token.clone()
+    }
+
+    pub fn set_cancel_token(&mut self, cancel_token: CancellationToken) {
+        self.cancel_token = cancel_token;
+    }
+
+    // give up with LoginTimeout when the user didn't log in in time
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    // a fixed port, when the redirect uri of the azure app has one
+    pub fn set_port(&mut self, port: u16) {
+        self.port = port;
+    }
+
+    pub(crate) fn into_parts(self) -> (OnUrl, CancellationToken, Option<Duration>, u16) {
+        (self.on_url, self.cancel_token, self.timeout, self.port)
+    }
+}
+
+// secret of the login (code verifier), and its hash sent in the url of the login page (code challenge)
+pub(crate) struct Pkce {
+    pub verifier: String,
+    pub challenge: String,
+}
+
+impl Pkce {
+    pub(crate) fn new() -> Result<Self> {
+        let verifier = URL_SAFE_NO_PAD.encode(random_bytes::<32>()?);
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+        Ok(Self { verifier, challenge })
+    }
+}
+
+pub(crate) fn random_state() -> Result<String> {
+    Ok(hex::encode(random_bytes::<16>()?))
+}
+
+fn random_bytes<const N: usize>() -> Result<[u8; N]> {
+    let mut bytes = [0; N];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|e| LauncherError::Auth(format!("no random bytes for the login: {}", e)))?;
+    Ok(bytes)
+}
+
+pub(crate) fn authorize_url(
+    endpoints: &Endpoints,
+    client_id: &str,
+    redirect_uri: &str,
+    pkce: &Pkce,
+    state: &str,
+) -> Result<String> {
+    let url = Url::parse_with_params(
+        &endpoints.authorize_url,
+        &[
+            ("client_id", client_id),
+            ("response_type", "code"),
+            ("redirect_uri", redirect_uri),
+            ("response_mode", "query"),
+            ("scope", SCOPE),
+            ("code_challenge", &pkce.challenge),
+            ("code_challenge_method", "S256"),
+            ("state", state),
+            ("prompt", "select_account"),
+        ],
+    )
+    .map_err(|e| LauncherError::Auth(format!("invalid authorize url {}: {}", endpoints.authorize_url, e)))?;
+    Ok(url.to_string())
+}
+
+// wait for the browser to be redirected to the listener, and give the code of the redirect
+// each connection is read in its own task : the browser can open connections it doesn't use (preconnect)
+pub(crate) async fn wait_for_code(listener: &TcpListener, state: &str) -> Result<String> {
+    // the tasks still running are aborted when it's dropped
+    let mut connections = JoinSet::new();
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (stream, _address) = accepted.map_err(|e| LauncherError::io("login listener", e))?;
+                connections.spawn(read_redirect(stream, state.to_string()));
+            }
+            Some(Ok(Some(result))) = connections.join_next() => return result,
+        }
+    }
+}
+
+// the code (or the error) of the redirect, None for the other requests answered with a 404
+// (favicon, or a request that doesn't have the state of the login : another page could send one to end the login)
+async fn read_redirect(mut stream: TcpStream, state: String) -> Option<Result<String>> {
+    let request_line = tokio::time::timeout(REQUEST_TIMEOUT, read_request_line(&mut stream))
+        .await
+        .ok()??;
+
+    let target = request_line.split_whitespace().nth(1).unwrap_or_default();
+    let Ok(url) = Url::parse(&format!("http://127.0.0.1{}", target)) else {
+        let _ = respond(&mut stream, "400 Bad Request", ERROR_PAGE).await;
+        return None;
+    };
+    let param = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _value)| key == name)
+            .map(|(_key, value)| value.to_string())
+    };
+
+    // the redirect must come from the login started by the launcher, the other requests are ignored
+    let result = match (param("state").as_deref() == Some(state.as_str()), param("code"), param("error")) {
+        (true, _, Some(error)) => Err(LauncherError::Auth(format!(
+            "login refused: {} ({})",
+            error,
+            param("error_description").unwrap_or_default()
+        ))),
+        (true, Some(code), None) => Ok(code),
+        _ => {
+            let _ = respond(&mut stream, "404 Not Found", "").await;
+            return None;
+        }
+    };
+
+    let page = if result.is_ok() { SUCCESS_PAGE } else { ERROR_PAGE };
+    let _ = respond(&mut stream, "200 OK", page).await;
+    Some(result)
+}
+
+async fn read_request_line(stream: &mut TcpStream) -> Option<String> {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).await.ok()? == 0 {
+        return None;
+    }
+    // the headers are not needed, but they are read so the browser doesn't get a reset connection
+    let mut header = String::new();
+    while reader.read_line(&mut header).await.is_ok_and(|read| read > 2) {
+        header.clear();
+    }
+    Some(request_line)
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, body: &str) -> std::io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
@@ -0,0 +1,63 @@
+use std::time::Duration;
+
+use tokio_util::sync::CancellationToken;
+
+// what the user must do to log in with the device code flow, given to the callback of DeviceCodeLogin
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceCodePrompt {
+    // page of Microsoft where the code is entered
+    pub verification_uri: String,
+    pub user_code: String,
+    // unix timestamp (in seconds) after which the code can't be used anymore
+    pub expires_at: u64,
+}
+
+pub(crate) type OnCode = Box<dyn FnOnce(&DeviceCodePrompt) + Send>;
+
+// how the device code is shown to the user, and when the login is given up
+// the default one prints the code on stdout, and waits until the code expires
+pub struct DeviceCodeLogin {
+    on_code: OnCode,
+    cancel_token: CancellationToken,
+    timeout: Option<Duration>,
+}
+
+impl Default for DeviceCodeLogin {
+    fn default() -> Self {
+        Self::new(|prompt| {
+            println!(
+                "Open this link in your browser {} and enter the following code: {}\nWaiting authentication...",
+                prompt.verification_uri, prompt.user_code
+            )
+        })
+    }
+}
+
+impl DeviceCodeLogin {
+    // on_code is called once the code is received, to show it in the launcher (a dialog for example)
+    pub fn new<F: FnOnce(&DeviceCodePrompt) + Send + 'static>(on_code: F) -> Self {
+        Self {
+            on_code: Box::new(on_code),
+            cancel_token: CancellationToken::new(),
+            timeout: None,
+        }
+    }
+
+    // cancel the token (when the user closes the dialog for example) to stop the login with LoginCancelled
+    pub fn cancel_token(&self) -> CancellationToken {
+        self.cancel_token.clone()
+    }
+
+    pub fn set_cancel_token(&mut self, cancel_token: CancellationToken) {
+        self.cancel_token = cancel_token;
+    }
+
+    // give up with LoginTimeout when the user didn't log in in time, even if the code is still valid
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    pub(crate) fn into_parts(self) -> (OnCode, CancellationToken, Option<Duration>) {
+        (self.on_code, self.cancel_token, self.timeout)
+    }
+}
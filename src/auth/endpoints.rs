@@ -0,0 +1,48 @@
+// urls of every step of the Microsoft authentication
+// the default ones are the real servers, they can be changed for a proxy or a mock server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoints {
+    // page the user logs in, for the authorization code flow
+    pub authorize_url: String,
+    pub device_code_url: String,
+    pub token_url: String,
+    pub xbox_live_url: String,
+    pub xsts_url: String,
+    pub minecraft_login_url: String,
+    pub profile_url: String,
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self {
+            authorize_url: "https://login.microsoftonline.com/consumers/oauth2/v2.0/authorize".to_string(),
+            device_code_url: "https://login.microsoftonline.com/consumers/oauth2/v2.0/devicecode".to_string(),
+            token_url: "https://login.microsoftonline.com/consumers/oauth2/v2.0/token".to_string(),
+            xbox_live_url: "https://user.auth.xboxlive.com/user/authenticate".to_string(),
+            xsts_url: "https://xsts.auth.xboxlive.com/xsts/authorize".to_string(),
+            minecraft_login_url: "https://api.minecraftservices.com/authentication/login_with_xbox".to_string(),
+            profile_url: "https://api.minecraftservices.com/minecraft/profile".to_string(),
+        }
+    }
+}
+
+impl Endpoints {
+    // every endpoint on the same server (http://127.0.0.1:8080 for example), at the path of the real one
+    pub fn with_base_url(base_url: &str) -> Self {
+        let base_url = base_url.trim_end_matches('/');
+        let rebase = |url: String| {
+            let path = url.splitn(4, '/').nth(3).unwrap_or_default();
+            format!("{}/{}", base_url, path)
+        };
+        let endpoints = Self::default();
+        Self {
+            authorize_url: rebase(endpoints.authorize_url),
+            device_code_url: rebase(endpoints.device_code_url),
+            token_url: rebase(endpoints.token_url),
+            xbox_live_url: rebase(endpoints.xbox_live_url),
+            xsts_url: rebase(endpoints.xsts_url),
+            minecraft_login_url: rebase(endpoints.minecraft_login_url),
+            profile_url: rebase(endpoints.profile_url),
+        }
+    }
+}
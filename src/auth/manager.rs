@@ -0,0 +1,237 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::auth::account::{Account, OfflineAccount};
+use crate::auth::auth_code::AuthCodeLogin;
+use crate::auth::device_code::DeviceCodeLogin;
+use crate::auth::endpoints::Endpoints;
+use crate::auth::session::Session;
+use crate::auth::Authenticator;
+use crate::error::{LauncherError, Result};
+
+// accounts of the launcher (Microsoft and offline) saved in a file (accounts.json in the launcher dir for example),
+// with the one used by default
+// the tokens are written as they are, the file must only be readable by the user
+pub struct AccountManager {
+    path: String,
+    client: reqwest::Client,
+    endpoints: Endpoints,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct StoredAccounts {
+    accounts: Vec<Account>,
+    // uuid of the default account
+    default_account: Option<String>,
+}
+
+impl AccountManager {
+    pub fn new<S: ToString>(path: S) -> Self {
+        Self::with_client(path, reqwest::Client::new())
+    }
+
+    // create a manager that shares the http client of the launcher
+    pub fn with_client<S: ToString>(path: S, client: reqwest::Client) -> Self {
+        Self {
+            path: path.to_string(),
+            client,
+            endpoints: Endpoints::default(),
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    // servers the accounts log in and are refreshed with, the Microsoft ones by default
+    pub fn set_endpoints(&mut self, endpoints: Endpoints) {
+        self.endpoints = endpoints;
+    }
+
+    pub fn list(&self) -> Result<Vec<Account>> {
+        Ok(self.read()?.accounts)
+    }
+
+    pub fn get(&self, uuid: &str) -> Result<Option<Account>> {
+        Ok(self.list()?.into_iter().find(|account| account.uuid() == uuid))
+    }
+
+    // add the account, or replace the saved one with the same uuid
+    // the first account added becomes the default one
+    pub fn add(&self, account: &Account) -> Result<()> {
+        self.update(|stored| {
+            match stored.accounts.iter_mut().find(|saved| saved.uuid() == account.uuid()) {
+                Some(saved) => *saved = account.clone(),
+                None => stored.accounts.push(account.clone()),
+            }
+            if stored.default_account.is_none() {
+                stored.default_account = Some(account.uuid().to_string());
+            }
+            Ok(())
+        })
+    }
+
+    // when the default account is removed, the first one left becomes the default
+    pub fn remove(&self, uuid: &str) -> Result<()> {
+        self.update(|stored| {
+            stored.accounts.retain(|account| account.uuid() != uuid);
+            if stored.default_account.as_deref() == Some(uuid) {
+                stored.default_account = stored.accounts.first().map(|account| account.uuid().to_string());
+            }
+            Ok(())
+        })
+    }
+
+    pub fn set_default(&self, uuid: &str) -> Result<()> {
+        self.update(|stored| {
+            if !stored.accounts.iter().any(|account| account.uuid() == uuid) {
+                return Err(LauncherError::AccountNotFound(uuid.to_string()));
+            }
+            stored.default_account = Some(uuid.to_string());
+            Ok(())
+        })
+    }
+
+    pub fn default_account(&self) -> Result<Option<Account>> {
+        let stored = self.read()?;
+        Ok(stored
+            .accounts
+            .into_iter()
+            .find(|account| Some(account.uuid()) == stored.default_account.as_deref()))
+    }
+
+    // log in a Microsoft account with the device code flow, and save it
+    pub async fn login_microsoft(&self, login: DeviceCodeLogin) -> Result<Account> {
+        let authenticator = self.authenticator().authenticate_ms_with(login).await?;
+        self.add_authenticated(&authenticator)
+    }
+
+    // log in a Microsoft account in the browser (authorization code flow), and save it
+    pub async fn login_microsoft_browser(&self, login: AuthCodeLogin) -> Result<Account> {
+        let authenticator = self.authenticator().authenticate_ms_browser(login).await?;
+        self.add_authenticated(&authenticator)
+    }
+
+    fn authenticator(&self) -> Authenticator {
+        let mut authenticator = Authenticator::with_client(self.client.clone());
+        authenticator.set_endpoints(self.endpoints.clone());
+        authenticator
+    }
+
+    fn add_authenticated(&self, authenticator: &Authenticator) -> Result<Account> {
+        let account = Account::Microsoft(
+            authenticator
+                .account()
+                .cloned()
+                .ok_or_else(|| LauncherError::Auth("no Microsoft account after the login".to_string()))?,
+        );
+        self.add(&account)?;
+        Ok(account)
+    }
+
+    // save an offline account with this name, its uuid is the one the game gives to the offline players
+    pub fn add_offline(&self, username: &str) -> Result<Account> {
+        let account = Account::Offline(OfflineAccount::new(username)?);
+        self.add(&account)?;
+        Ok(account)
+    }
+
+    // session to launch the game with the account, its tokens are refreshed (and saved) when they expired
+    pub async fn session(&self, uuid: &str) -> Result<Session> {
+        match self.get(uuid)?.ok_or_else(|| LauncherError::AccountNotFound(uuid.to_string()))? {
+            Account::Microsoft(account) => {
+                let mut authenticator = Authenticator::from_account(self.client.clone(), account.clone());
+                authenticator.set_endpoints(self.endpoints.clone());
+                authenticator.refresh().await?;
+                let refreshed = authenticator.account().cloned().unwrap_or(account.clone());
+                // the file is only written when the tokens (or the name of the profile) changed
+                // an account removed during the refresh isn't saved again
+                if refreshed != account {
+                    self.update(|stored| {
+                        if let Some(saved) = stored.accounts.iter_mut().find(|saved| saved.uuid() == uuid) {
+                            *saved = Account::Microsoft(refreshed.clone());
+                        }
+                        Ok(())
+                    })?;
+                }
+                Ok(Session::from_microsoft(&refreshed))
+            }
+            Account::Offline(account) => Ok(Session::from_offline(&account)),
+        }
+    }
+
+    pub async fn default_session(&self) -> Result<Session> {
+        let account = self
+            .default_account()?
+            .ok_or_else(|| LauncherError::AccountNotFound("default account".to_string()))?;
+        self.session(account.uuid()).await
+    }
+
+    // read, change and write the accounts while holding the lock of the file, so the changes of two launchers
+    // running at the same time don't overwrite each other
+    fn update<F: FnOnce(&mut StoredAccounts) -> Result<()>>(&self, change: F) -> Result<()> {
+        let _lock = self.lock()?;
+        let mut stored = self.read()?;
+        change(&mut stored)?;
+        self.write(&stored)
+    }
+
+    // the lock is taken on a file next to the accounts (accounts.json.lock), the accounts file is replaced by each write
+    // it's released when the returned file is dropped
+    fn lock(&self) -> Result<fs::File> {
+        if let Some(parent) = Path::new(&self.path).parent() {
+            fs::create_dir_all(parent).map_err(|e| LauncherError::io(parent.display(), e))?;
+        }
+        let lock_path = format!("{}.lock", self.path);
+        let lock = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&lock_path)
+            .map_err(|e| LauncherError::io(&lock_path, e))?;
+        lock.lock().map_err(|e| LauncherError::io(&lock_path, e))?;
+        Ok(lock)
+    }
+
+    fn read(&self) -> Result<StoredAccounts> {
+        match fs::read_to_string(&self.path) {
+            Ok(json) => serde_json::from_str(&json).map_err(|e| LauncherError::manifest(&self.path, e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(StoredAccounts::default()),
+            Err(e) => Err(LauncherError::io(&self.path, e)),
+        }
+    }
+
+    // only called by update, with the lock held
+    fn write(&self, stored: &StoredAccounts) -> Result<()> {
+        let json = serde_json::to_string_pretty(stored).map_err(|e| LauncherError::manifest(&self.path, e))?;
+
+        // written next to the file then renamed : the tokens are never readable by the others, even for a moment,
+        // and a crash while writing doesn't lose the accounts
+        let temp_path = format!("{}.tmp", self.path);
+        // a temp file left by a crash can have other permissions, it's created again
+        let _ = fs::remove_file(&temp_path);
+        let mut file = secret_file_options()
+            .open(&temp_path)
+            .map_err(|e| LauncherError::io(&temp_path, e))?;
+        file.write_all(json.as_bytes())
+            .and_then(|_| file.sync_all())
+            .map_err(|e| LauncherError::io(&temp_path, e))?;
+        fs::rename(&temp_path, &self.path).map_err(|e| LauncherError::io(&self.path, e))
+    }
+}
+
+// a new file only readable and writable by the user
+fn secret_file_options() -> fs::OpenOptions {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+}
@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use reqwest::{Client, StatusCode};
+use serde::de::DeserializeOwned;
+use serde_derive::Deserialize;
+use serde_json::json;
+
+use crate::auth::account::{unix_timestamp, Token};
+use crate::auth::endpoints::Endpoints;
+use crate::error::{LauncherError, Result};
+
+// ------------------------------------------------------------------------------------- //
+// Steps of the Microsoft authentication : Microsoft -> Xbox Live -> XSTS -> Minecraft //
+// ------------------------------------------------------------------------------------- //
+
+pub(crate) const SCOPE: &str = "XboxLive.signin offline_access";
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct DeviceCode {
+    pub user_code: String,
+    pub device_code: String,
+    pub verification_uri: String,
+    pub expires_in: u64,
+    pub interval: u64,
+}
+
+// tokens given by the Microsoft token endpoint
+pub(crate) struct MicrosoftTokens {
+    pub access_token: Token,
+    pub refresh_token: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: String,
+    expires_in: u64,
+}
+
+#[derive(Deserialize)]
+struct TokenError {
+    error: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct XboxResponse {
+    not_after: String,
+    token: String,
+    display_claims: HashMap<String, Vec<HashMap<String, String>>>,
+}
+
+#[derive(Deserialize)]
+struct MinecraftResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+// token of Xbox Live or XSTS, with the user hash needed by the next step
+pub(crate) struct XboxToken {
+    pub token: Token,
+    pub user_hash: String,
+}
+
+pub(crate) async fn request_device_code(client: &Client, endpoints: &Endpoints, client_id: &str) -> Result<DeviceCode> {
+    let url = endpoints.device_code_url.as_str();
+    let response = client
+        .post(url)
+        .form(&[("client_id", client_id), ("scope", SCOPE)])
+        .send()
+        .await
+        .and_then(|res| res.error_for_status())
+        .map_err(|e| LauncherError::network(url, e))?;
+    read_json(url, response).await
+}
+
+// ask the token endpoint until the user entered the code, or the code expired
+pub(crate) async fn poll_device_code(
+    client: &Client,
+    endpoints: &Endpoints,
+    client_id: &str,
+    device_code: &DeviceCode,
+) -> Result<MicrosoftTokens> {
+    let mut interval = device_code.interval.max(1);
+    let expires_at = unix_timestamp() + device_code.expires_in;
+
+    loop {
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+        let form = [
+            ("client_id", client_id),
+            ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
+            ("device_code", device_code.device_code.as_str()),
+        ];
+        match request_tokens(client, endpoints, &form).await? {
+            Ok(tokens) => return Ok(tokens),
+            Err(error) if error == "authorization_pending" => {}
+            Err(error) if error == "slow_down" => interval += 5,
+            Err(error) => return Err(LauncherError::Auth(format!("device code refused: {}", error))),
+        }
+        if unix_timestamp() >= expires_at {
+            return Err(LauncherError::LoginTimeout);
+        }
+    }
+}
+
+// new Microsoft tokens from the refresh token, without asking anything to the user
+pub(crate) async fn refresh_microsoft_token(
+    client: &Client,
+    endpoints: &Endpoints,
+    client_id: &str,
+    refresh_token: &str,
+) -> Result<MicrosoftTokens> {
+    let form = [
+        ("client_id", client_id),
+        ("grant_type", "refresh_token"),
+        ("refresh_token", refresh_token),
+        ("scope", SCOPE),
+    ];
+    request_tokens(client, endpoints, &form)
+        .await?
+        .map_err(|error| LauncherError::Auth(format!("the session expired, log in again ({})", error)))
+}
+
+// the tokens, or the oauth error code when the endpoint refused the request
+pub(crate) async fn request_tokens(
+    client: &Client,
+    endpoints: &Endpoints,
+    form: &[(&str, &str)],
+) -> Result<std::result::Result<MicrosoftTokens, String>> {
+    let url = endpoints.token_url.as_str();
+    let response = client
+        .post(url)
+        .form(form)
+        .send()
+        .await
+        .map_err(|e| LauncherError::network(url, e))?;
+
+    if response.status() == StatusCode::BAD_REQUEST || response.status() == StatusCode::UNAUTHORIZED {
+        let error: TokenError = read_json(url, response).await?;
+        return Ok(Err(error.error));
+    }
+    let response = response
+        .error_for_status()
+        .map_err(|e| LauncherError::network(url, e))?;
+    let tokens: TokenResponse = read_json(url, response).await?;
+    Ok(Ok(MicrosoftTokens {
+        access_token: Token::expiring_in(tokens.access_token, tokens.expires_in),
+        refresh_token: tokens.refresh_token,
+    }))
+}
+
+pub(crate) async fn authenticate_xbox_live(
+    client: &Client,
+    endpoints: &Endpoints,
+    microsoft_token: &str,
+) -> Result<XboxToken> {
+    let body = json!({
+        "Properties": {
+            "AuthMethod": "RPS",
+            "SiteName": "user.auth.xboxlive.com",
+            "RpsTicket": format!("d={}", microsoft_token)
+        },
+        "RelyingParty": "http://auth.xboxlive.com",
+        "TokenType": "JWT"
+    });
+    xbox_request(client, &endpoints.xbox_live_url, body).await
+}
+
+pub(crate) async fn authorize_xsts(client: &Client, endpoints: &Endpoints, xbox_live_token: &str) -> Result<XboxToken> {
+    let body = json!({
+        "Properties": {
+            "SandboxId": "RETAIL",
+            "UserTokens": [xbox_live_token]
+        },
+        "RelyingParty": "rp://api.minecraftservices.com/",
+        "TokenType": "JWT"
+    });
+    xbox_request(client, &endpoints.xsts_url, body).await
+}
+
+async fn xbox_request(client: &Client, url: &str, body: serde_json::Value) -> Result<XboxToken> {
+    let response = client
+        .post(url)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| LauncherError::network(url, e))?;
+
+    // the account can't play (no Xbox profile, child account...), the reason is in XErr
+    if response.status() == StatusCode::UNAUTHORIZED {
+        let error: serde_json::Value = read_json(url, response).await?;
+        return Err(LauncherError::Auth(format!(
+            "Xbox Live refused the account (XErr {})",
+            error.get("XErr").unwrap_or(&serde_json::Value::Null)
+        )));
+    }
+    let response = response.error_for_status().map_err(|e| LauncherError::network(url, e))?;
+    let xbox: XboxResponse = read_json(url, response).await?;
+
+    let user_hash = xbox
+        .display_claims
+        .get("xui")
+        .and_then(|claims| claims.first())
+        .and_then(|claim| claim.get("uhs"))
+        .cloned()
+        .ok_or_else(|| LauncherError::Auth("no user hash in the Xbox Live answer".to_string()))?;
+    Ok(XboxToken {
+        token: Token {
+            value: xbox.token,
+            expires_at: parse_timestamp(&xbox.not_after).unwrap_or(0),
+        },
+        user_hash,
+    })
+}
+
+pub(crate) async fn login_with_xbox(
+    client: &Client,
+    endpoints: &Endpoints,
+    user_hash: &str,
+    xsts_token: &str,
+) -> Result<Token> {
+    let url = endpoints.minecraft_login_url.as_str();
+    let body = json!({ "identityToken": format!("XBL3.0 x={};{}", user_hash, xsts_token) });
+    let response = client
+        .post(url)
+        .json(&body)
+        .send()
+        .await
+        .and_then(|res| res.error_for_status())
+        .map_err(|e| LauncherError::network(url, e))?;
+    let minecraft: MinecraftResponse = read_json(url, response).await?;
+    Ok(Token::expiring_in(minecraft.access_token, minecraft.expires_in))
+}
+
+async fn read_json<T: DeserializeOwned>(url: &str, response: reqwest::Response) -> Result<T> {
+    let text = response.text().await.map_err(|e| LauncherError::network(url, e))?;
+    serde_json::from_str(&text).map_err(|e| LauncherError::manifest(url, e))
+}
+
+// unix timestamp of a date like 2024-06-11T04:00:00.1234567Z (the NotAfter of Xbox Live)
+pub(crate) fn parse_timestamp(date: &str) -> Option<u64> {
+    let (day, time) = date.split_once('T')?;
+    let mut day = day.split('-').map(|part| part.parse::<i64>());
+    let (year, month, day) = (day.next()?.ok()?, day.next()?.ok()?, day.next()?.ok()?);
+    let time = time.trim_end_matches('Z');
+    let time = time.split('.').next()?;
+    let mut time = time.split(':').map(|part| part.parse::<i64>());
+    let (hours, minutes, seconds) = (time.next()?.ok()?, time.next()?.ok()?, time.next()?.ok()?);
+
+    // days since 1970-01-01 of the date (proleptic gregorian calendar)
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    u64::try_from(days * 86400 + hours * 3600 + minutes * 60 + seconds).ok()
+}
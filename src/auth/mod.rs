@@ -1,71 +1,253 @@
-use std::error::Error;
+use std::future::Future;
+use std::time::Duration;
 
-use mc_auth::AuthFlow;
 use reqwest::header::{AUTHORIZATION, HeaderMap};
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
 
+use crate::auth::account::{unix_timestamp, MicrosoftAccount};
+use crate::auth::auth_code::{AuthCodeLogin, OnUrl, Pkce};
+use crate::auth::device_code::{DeviceCodeLogin, DeviceCodePrompt, OnCode};
+use crate::auth::endpoints::Endpoints;
+use crate::auth::microsoft::MicrosoftTokens;
 use crate::auth::profile::User;
+use crate::error::{LauncherError, Result};
 
-mod profile;
+pub mod account;
+pub mod auth_code;
+pub mod device_code;
+pub mod endpoints;
+pub mod manager;
+pub(crate) mod microsoft;
+pub mod profile;
+pub mod session;
 
-const CLIENT_ID: &str = "f8c516cc-122f-4701-89eb-c9bbf789028a";
+pub(crate) const CLIENT_ID: &str = "f8c516cc-122f-4701-89eb-c9bbf789028a";
 
 pub struct Authenticator {
+    client: reqwest::Client,
+    endpoints: Endpoints,
     access_token: String,
+    // tokens of the Microsoft account, to save in an AccountManager and refresh later
+    account: Option<MicrosoftAccount>,
+}
+
+impl Default for Authenticator {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Authenticator {
-    pub fn authenticate_ms(&self) -> Result<Authenticator, Box<dyn Error>> {
-        let mut auth = AuthFlow::new(CLIENT_ID);
-        let code_res = auth.request_code()?;
+    // log in with the device code flow : the user enters a code on the Microsoft website
+    // the code is printed on stdout, see authenticate_ms_with to show it in the launcher
+    pub async fn authenticate_ms(&self) -> Result<Authenticator> {
+        self.authenticate_ms_with(DeviceCodeLogin::default()).await
+    }
 
-        println!(
-            "Open this link in your browser {} and enter the following code: {}\nWaiting authentication...",
-            code_res.verification_uri, code_res.user_code
-        );
+    // log in with the device code flow, the code is given to the callback of the login
+    pub async fn authenticate_ms_with(&self, login: DeviceCodeLogin) -> Result<Authenticator> {
+        let (on_code, cancel_token, timeout) = login.into_parts();
+        cancellable(cancel_token, timeout, self.device_code_login(on_code)).await
+    }
 
-        auth.wait_for_login()?;
-        auth.login_in_xbox_live()?;
+    // log in with the authorization code flow : the user logs in the browser, on the page given to the
+    // callback of the login, which then redirects to a listener of the launcher on 127.0.0.1
+    pub async fn authenticate_ms_browser(&self, login: AuthCodeLogin) -> Result<Authenticator> {
+        let (on_url, cancel_token, timeout, port) = login.into_parts();
+        cancellable(cancel_token, timeout, self.auth_code_login(on_url, port)).await
+    }
+
+    async fn device_code_login(&self, on_code: OnCode) -> Result<Authenticator> {
+        let device_code = microsoft::request_device_code(&self.client, &self.endpoints, CLIENT_ID).await?;
+        on_code(&DeviceCodePrompt {
+            verification_uri: device_code.verification_uri.clone(),
+            user_code: device_code.user_code.clone(),
+            expires_at: unix_timestamp() + device_code.expires_in,
+        });
+        let tokens = microsoft::poll_device_code(&self.client, &self.endpoints, CLIENT_ID, &device_code).await?;
+        self.login_with_tokens(tokens).await
+    }
+
+    async fn auth_code_login(&self, on_url: OnUrl, port: u16) -> Result<Authenticator> {
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .await
+            .map_err(|e| LauncherError::io(format!("127.0.0.1:{}", port), e))?;
+        let port = listener
+            .local_addr()
+            .map_err(|e| LauncherError::io("login listener", e))?
+            .port();
+        // the ip of the listener, localhost could be resolved to ::1 where nothing listens
+        let redirect_uri = format!("http://127.0.0.1:{}", port);
+        let pkce = Pkce::new()?;
+        let state = auth_code::random_state()?;
 
-        let minecraft = auth.login_in_minecraft()?;
+        on_url(&auth_code::authorize_url(&self.endpoints, CLIENT_ID, &redirect_uri, &pkce, &state)?);
+        let code = auth_code::wait_for_code(&listener, &state).await?;
 
-        Ok(Authenticator {
-            access_token: minecraft.access_token.to_string(),
-        })
+        let form = [
+            ("client_id", CLIENT_ID),
+            ("grant_type", "authorization_code"),
+            ("code", code.as_str()),
+            ("redirect_uri", redirect_uri.as_str()),
+            ("code_verifier", pkce.verifier.as_str()),
+            ("scope", microsoft::SCOPE),
+        ];
+        let tokens = microsoft::request_tokens(&self.client, &self.endpoints, &form)
+            .await?
+            .map_err(|error| LauncherError::Auth(format!("authorization code refused: {}", error)))?;
+        self.login_with_tokens(tokens).await
     }
 
-    pub fn get_profile(&self) -> Result<User, Box<dyn Error>> {
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            AUTHORIZATION,
-            format!("Bearer {}", self.access_token).parse().unwrap(),
+    // the rest of the chain (Xbox Live -> XSTS -> Minecraft) from the Microsoft tokens
+    async fn login_with_tokens(&self, tokens: MicrosoftTokens) -> Result<Authenticator> {
+        let mut authenticator = Self::from_account(
+            self.client.clone(),
+            MicrosoftAccount {
+                microsoft_token: tokens.access_token,
+                refresh_token: tokens.refresh_token,
+                ..MicrosoftAccount::default()
+            },
         );
-        let client = reqwest::Client::new();
-        let mut user: User = User::new();
-
-        let runtime = tokio::runtime::Runtime::new().unwrap();
-        runtime.block_on(async {
-            if let Ok(res) = client
-                .get("https://api.minecraftservices.com/minecraft/profile")
-                .headers(headers)
-                .send()
-                .await
-            {
-                if let Ok(text) = res.text().await {
-                    println!("{}", text);
-                    if let Ok(parse) = serde_json::from_str(&text) {
-                        user = parse
+        authenticator.set_endpoints(self.endpoints.clone());
+        authenticator.refresh().await?;
+        Ok(authenticator)
+    }
+
+    // renew the expired tokens of the account, starting from the last step that is still valid
+    // when even the Microsoft token is expired, the refresh token is used, so the user doesn't log in again
+    pub async fn refresh(&mut self) -> Result<()> {
+        let mut account = self
+            .account
+            .clone()
+            .ok_or_else(|| LauncherError::Auth("no Microsoft account to refresh".to_string()))?;
+
+        if account.minecraft_token.is_expired() {
+            if account.xsts_token.is_expired() {
+                if account.xbox_live_token.is_expired() {
+                    if account.microsoft_token.is_expired() {
+                        let tokens =
+                            microsoft::refresh_microsoft_token(&self.client, &self.endpoints, CLIENT_ID, &account.refresh_token)
+                                .await?;
+                        account.microsoft_token = tokens.access_token;
+                        account.refresh_token = tokens.refresh_token;
                     }
+                    let xbox_live = microsoft::authenticate_xbox_live(
+                        &self.client,
+                        &self.endpoints,
+                        &account.microsoft_token.value,
+                    )
+                    .await?;
+                    account.xbox_live_token = xbox_live.token;
                 }
+                let xsts =
+                    microsoft::authorize_xsts(&self.client, &self.endpoints, &account.xbox_live_token.value).await?;
+                account.xsts_token = xsts.token;
+                account.user_hash = xsts.user_hash;
             }
-        });
+            account.minecraft_token = microsoft::login_with_xbox(
+                &self.client,
+                &self.endpoints,
+                &account.user_hash,
+                &account.xsts_token.value,
+            )
+            .await?;
 
-        println!("{}", user.name());
+            // the name of the profile can change between two logins
+            self.access_token = account.minecraft_token.value.clone();
+            let profile = self.get_profile().await?;
+            account.uuid = profile.id().to_string();
+            account.username = profile.name().to_string();
+        }
 
-        Ok(user)
+        self.access_token = account.minecraft_token.value.clone();
+        self.account = Some(account);
+        Ok(())
+    }
+
+    pub async fn get_profile(&self) -> Result<User> {
+        let url = self.endpoints.profile_url.as_str();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            AUTHORIZATION,
+            format!("Bearer {}", self.access_token)
+                .parse()
+                .map_err(|_| LauncherError::Auth("invalid access token".to_string()))?,
+        );
+        let text = self
+            .client
+            .get(url)
+            .headers(headers)
+            .send()
+            .await
+            .and_then(|res| res.error_for_status())
+            .map_err(|e| LauncherError::network(url, e))?
+            .text()
+            .await
+            .map_err(|e| LauncherError::network(url, e))?;
+
+        serde_json::from_str(&text).map_err(|e| LauncherError::manifest("profile", e))
     }
     pub fn new() -> Self {
+        Self::with_client(reqwest::Client::new())
+    }
+
+    // create an authenticator that shares the http client of the launcher
+    pub fn with_client(client: reqwest::Client) -> Self {
         Self {
+            client,
+            endpoints: Endpoints::default(),
             access_token: String::new(),
+            account: None,
+        }
+    }
+
+    // authenticator of an account loaded from an AccountManager, call refresh before using its access token
+    pub fn from_account(client: reqwest::Client, account: MicrosoftAccount) -> Self {
+        Self {
+            client,
+            endpoints: Endpoints::default(),
+            access_token: account.minecraft_token.value.clone(),
+            account: Some(account),
         }
     }
+
+    // servers of the authentication, the Microsoft ones by default
+    pub fn set_endpoints(&mut self, endpoints: Endpoints) {
+        self.endpoints = endpoints;
+    }
+
+    pub fn endpoints(&self) -> &Endpoints {
+        &self.endpoints
+    }
+
+    pub fn access_token(&self) -> &str {
+        &self.access_token
+    }
+
+    pub fn account(&self) -> Option<&MicrosoftAccount> {
+        self.account.as_ref()
+    }
+}
+
+// stop the login with LoginCancelled when the token is cancelled, or with LoginTimeout after the timeout
+async fn cancellable<T>(
+    cancel_token: CancellationToken,
+    timeout: Option<Duration>,
+    login: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    let login = async {
+        match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, login)
+                .await
+                .map_err(|_| LauncherError::LoginTimeout)?,
+            None => login.await,
+        }
+    };
+
+    tokio::select! {
+        biased;
+        _ = cancel_token.cancelled() => Err(LauncherError::LoginCancelled),
+        result = login => result,
+    }
 }
@@ -58,6 +58,12 @@ pub struct User {
     capes: Vec<Cape>,
     profileActions: Value,
 }
+impl Default for User {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl User {
     pub fn new() -> Self {
         Self {
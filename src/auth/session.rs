@@ -0,0 +1,56 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+
+use crate::auth::account::{MicrosoftAccount, OfflineAccount};
+use crate::auth::CLIENT_ID;
+
+// what the game needs to know about the player, given to GameLauncher::launch
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    pub username: String,
+    pub uuid: String,
+    pub access_token: String,
+    // msa for the Microsoft accounts, legacy for the offline ones
+    pub user_type: String,
+    // id of the Xbox user, used by the game for the telemetry
+    pub xuid: String,
+    pub client_id: String,
+}
+
+impl Session {
+    // the tokens of the account must have been refreshed before
+    pub fn from_microsoft(account: &MicrosoftAccount) -> Self {
+        Self {
+            username: account.username.clone(),
+            uuid: account.uuid.clone(),
+            access_token: account.minecraft_token.value.clone(),
+            user_type: "msa".to_string(),
+            xuid: xuid_from_token(&account.minecraft_token.value).unwrap_or_else(|| "0".to_string()),
+            client_id: CLIENT_ID.to_string(),
+        }
+    }
+
+    // the game doesn't check the token of an offline account, it only has to be there
+    pub fn from_offline(account: &OfflineAccount) -> Self {
+        Self {
+            username: account.username.clone(),
+            uuid: account.uuid.clone(),
+            access_token: "0".to_string(),
+            user_type: "legacy".to_string(),
+            xuid: "0".to_string(),
+            client_id: String::new(),
+        }
+    }
+}
+
+// the minecraft access token is a jwt, its payload has the xuid of the player
+pub(crate) fn xuid_from_token(token: &str) -> Option<String> {
+    let payload = token.split('.').nth(1)?;
+    let payload = URL_SAFE_NO_PAD.decode(payload.trim_end_matches('=')).ok()?;
+    let payload: serde_json::Value = serde_json::from_slice(&payload).ok()?;
+    match payload.get("xuid")? {
+        serde_json::Value::String(xuid) => Some(xuid.clone()),
+        serde_json::Value::Number(xuid) => Some(xuid.to_string()),
+        _ => None,
+    }
+}
@@ -0,0 +1,216 @@
+use std::future::Future;
+use std::sync::OnceLock;
+
+use tokio::runtime::Runtime;
+
+use crate::auth::account::Account;
+use crate::auth::auth_code::AuthCodeLogin;
+use crate::auth::device_code::DeviceCodeLogin;
+use crate::auth::profile::User;
+use crate::auth::session::Session;
+use crate::error::{LauncherError, Result};
+use crate::update::downloads::InstallReport;
+use crate::update::repair::RepairReport;
+use crate::update::structs::mc_versions::Versions;
+
+// --------------------------------------------------------------------------- //
+// Synchronous wrappers around the async api (enabled by the blocking feature) //
+// Don't use them from inside a tokio runtime: they would panic                //
+// --------------------------------------------------------------------------- //
+
+// run a future to completion on a runtime shared by all the blocking wrappers
+fn block_on<T>(future: impl Future<Output = Result<T>>) -> Result<T> {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+    if let Some(runtime) = RUNTIME.get() {
+        return runtime.block_on(future);
+    }
+    let runtime = Runtime::new().map_err(|e| LauncherError::io("tokio runtime", e))?;
+    RUNTIME.get_or_init(|| runtime).block_on(future)
+}
+
+pub struct Updater {
+    inner: crate::update::updater::Updater,
+}
+
+impl Updater {
+    pub fn new<V: ToString>(version: V) -> Self {
+        Self {
+            inner: crate::update::updater::Updater::new(version),
+        }
+    }
+
+    pub fn set_relative_local_dir_path(&mut self, local_dir_path: &str) {
+        self.inner.set_relative_local_dir_path(local_dir_path);
+    }
+
+    pub fn get_versions_list(&self) -> Result<Versions> {
+        block_on(self.inner.get_versions_list())
+    }
+
+    pub fn update_files_list(&mut self) -> Result<()> {
+        block_on(self.inner.update_files_list())
+    }
+
+    pub fn install_files(&mut self) -> Result<InstallReport> {
+        block_on(self.inner.install_files())
+    }
+
+    pub fn repair(&mut self, redownload: bool) -> Result<RepairReport> {
+        block_on(self.inner.repair(redownload))
+    }
+
+    pub fn inner(&self) -> &crate::update::updater::Updater {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut crate::update::updater::Updater {
+        &mut self.inner
+    }
+}
+
+pub struct GameLauncher {
+    inner: crate::launch::GameLauncher,
+}
+
+impl GameLauncher {
+    pub fn new(
+        version: String,
+        local_dir_path: String,
+        game_args: Vec<String>,
+        jvm_args: Vec<String>,
+    ) -> Self {
+        Self {
+            inner: crate::launch::GameLauncher::new(version, local_dir_path, game_args, jvm_args),
+        }
+    }
+
+    pub fn with_local_dir_path(
+        version: String,
+        local_dir_path: String,
+        game_args: Vec<String>,
+        jvm_args: Vec<String>,
+    ) -> Self {
+        Self {
+            inner: crate::launch::GameLauncher::with_local_dir_path(version, local_dir_path, game_args, jvm_args),
+        }
+    }
+
+    pub fn launch(&self, session: &Session) -> Result<GameProcess> {
+        block_on(self.inner.launch(session)).map(|inner| GameProcess { inner })
+    }
+
+    pub fn inner(&self) -> &crate::launch::GameLauncher {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut crate::launch::GameLauncher {
+        &mut self.inner
+    }
+}
+
+// the receivers of take_stdout / take_stderr can be read without a runtime with blocking_recv
+pub struct GameProcess {
+    inner: crate::launch::process::GameProcess,
+}
+
+impl GameProcess {
+    pub fn wait(&mut self) -> Result<crate::launch::process::GameExit> {
+        block_on(self.inner.wait())
+    }
+
+    pub fn kill(&mut self) -> Result<()> {
+        block_on(self.inner.kill())
+    }
+
+    pub fn inner(&self) -> &crate::launch::process::GameProcess {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut crate::launch::process::GameProcess {
+        &mut self.inner
+    }
+}
+
+#[derive(Default)]
+pub struct Authenticator {
+    inner: crate::auth::Authenticator,
+}
+
+impl Authenticator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn authenticate_ms(&self) -> Result<Authenticator> {
+        Ok(Authenticator {
+            inner: block_on(self.inner.authenticate_ms())?,
+        })
+    }
+
+    // the cancel token of the login can be cancelled from another thread
+    pub fn authenticate_ms_with(&self, login: DeviceCodeLogin) -> Result<Authenticator> {
+        Ok(Authenticator {
+            inner: block_on(self.inner.authenticate_ms_with(login))?,
+        })
+    }
+
+    pub fn authenticate_ms_browser(&self, login: AuthCodeLogin) -> Result<Authenticator> {
+        Ok(Authenticator {
+            inner: block_on(self.inner.authenticate_ms_browser(login))?,
+        })
+    }
+
+    pub fn refresh(&mut self) -> Result<()> {
+        block_on(self.inner.refresh())
+    }
+
+    pub fn get_profile(&self) -> Result<User> {
+        block_on(self.inner.get_profile())
+    }
+
+    pub fn inner(&self) -> &crate::auth::Authenticator {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut crate::auth::Authenticator {
+        &mut self.inner
+    }
+}
+
+pub struct AccountManager {
+    inner: crate::auth::manager::AccountManager,
+}
+
+impl AccountManager {
+    pub fn new<S: ToString>(path: S) -> Self {
+        Self {
+            inner: crate::auth::manager::AccountManager::new(path),
+        }
+    }
+
+    pub fn login_microsoft(&self, login: DeviceCodeLogin) -> Result<Account> {
+        block_on(self.inner.login_microsoft(login))
+    }
+
+    pub fn login_microsoft_browser(&self, login: AuthCodeLogin) -> Result<Account> {
+        block_on(self.inner.login_microsoft_browser(login))
+    }
+
+    pub fn session(&self, uuid: &str) -> Result<Session> {
+        block_on(self.inner.session(uuid))
+    }
+
+    pub fn default_session(&self) -> Result<Session> {
+        block_on(self.inner.default_session())
+    }
+
+    // the other functions of the manager (list, add, remove, set_default...) are synchronous
+    pub fn inner(&self) -> &crate::auth::manager::AccountManager {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut crate::auth::manager::AccountManager {
+        &mut self.inner
+    }
+}
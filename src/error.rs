@@ -0,0 +1,127 @@
+use std::fmt::{Display, Formatter};
+
+// ------------------------------------------------ //
+// Error type shared by every module of the library //
+// ------------------------------------------------ //
+
+pub type Result<T> = std::result::Result<T, LauncherError>;
+
+#[derive(Debug)]
+pub enum LauncherError {
+    // a request could not be sent, or the server answered with an error status
+    Network { url: String, source: reqwest::Error },
+    // the downloaded (or existing) file doesn't match the hash of the manifest
+    HashMismatch {
+        path: String,
+        expected: String,
+        found: String,
+    },
+    // a json from the Mojang / Azul apis could not be parsed
+    ManifestParse {
+        manifest: String,
+        source: serde_json::Error,
+    },
+    // the requested version of the game doesn't exist in the version manifest
+    VersionNotFound(String),
+    // no java runtime could be found (or downloaded) for the version
+    JavaRuntimeMissing(String),
+    // an error happened during the authentication process
+    Auth(String),
+    // the login was cancelled with the cancel token of DeviceCodeLogin
+    LoginCancelled,
+    // the user didn't log in before the timeout, or before the expiry of the code
+    LoginTimeout,
+    // no instance with this name in the launcher directory
+    InstanceNotFound(String),
+    // an instance could not be created / renamed (invalid name, name already used...)
+    InvalidInstance(String),
+    // no account with this uuid saved by the AccountManager
+    AccountNotFound(String),
+    // an error on the file system, with the path of the file that caused it
+    Io { path: String, source: std::io::Error },
+}
+
+impl LauncherError {
+    pub(crate) fn network(url: &str, source: reqwest::Error) -> Self {
+        LauncherError::Network {
+            url: url.to_string(),
+            source,
+        }
+    }
+
+    pub(crate) fn manifest<S: ToString>(manifest: S, source: serde_json::Error) -> Self {
+        LauncherError::ManifestParse {
+            manifest: manifest.to_string(),
+            source,
+        }
+    }
+
+    // tells if the operation that failed can succeed when tried again
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            LauncherError::Network { source, .. } => match source.status() {
+                // no answer from the server (timeout, connection reset...)
+                None => true,
+                Some(status) => {
+                    status.is_server_error()
+                        || status == reqwest::StatusCode::REQUEST_TIMEOUT
+                        || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                }
+            },
+            // the file may have been corrupted during the transfer
+            LauncherError::HashMismatch { .. } => true,
+            _ => false,
+        }
+    }
+
+    pub(crate) fn io<S: ToString>(path: S, source: std::io::Error) -> Self {
+        LauncherError::Io {
+            path: path.to_string(),
+            source,
+        }
+    }
+}
+
+impl Display for LauncherError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LauncherError::Network { url, source } => {
+                write!(f, "Network error on {}: {}", url, source)
+            }
+            LauncherError::HashMismatch {
+                path,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Hash mismatch for file at {} (expected {}, found {})",
+                path, expected, found
+            ),
+            LauncherError::ManifestParse { manifest, source } => {
+                write!(f, "Error parsing {} manifest: {}", manifest, source)
+            }
+            LauncherError::VersionNotFound(version) => write!(f, "Version not found: {}", version),
+            LauncherError::JavaRuntimeMissing(message) => {
+                write!(f, "Java runtime missing: {}", message)
+            }
+            LauncherError::Auth(message) => write!(f, "Authentication error: {}", message),
+            LauncherError::LoginCancelled => write!(f, "Login cancelled"),
+            LauncherError::LoginTimeout => write!(f, "Login timed out"),
+            LauncherError::InstanceNotFound(name) => write!(f, "Instance not found: {}", name),
+            LauncherError::InvalidInstance(message) => write!(f, "Invalid instance: {}", message),
+            LauncherError::AccountNotFound(uuid) => write!(f, "Account not found: {}", uuid),
+            LauncherError::Io { path, source } => write!(f, "I/O error on {}: {}", path, source),
+        }
+    }
+}
+
+impl std::error::Error for LauncherError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LauncherError::Network { source, .. } => Some(source),
+            LauncherError::ManifestParse { source, .. } => Some(source),
+            LauncherError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,323 @@
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use reqwest::Client;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::auth::account::unix_timestamp;
+use crate::auth::session::Session;
+use crate::error::{LauncherError, Result};
+use crate::launch::process::GameProcess;
+use crate::launch::GameLauncher;
+use crate::update::updater::Updater;
+use crate::update::utils::{get_relative_local_dir_path, run_blocking};
+
+// ---------------------------------------------------------------------- //
+// Instances : a version of the game with its own dir, settings and stats //
+// ---------------------------------------------------------------------- //
+
+const INSTANCES_DIR: &str = "instances";
+const INSTANCE_FILE: &str = "instance.json";
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Loader {
+    #[default]
+    Vanilla,
+    Fabric,
+    Quilt,
+    Forge,
+    NeoForge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Resolution {
+    pub width: u32,
+    pub height: u32,
+}
+
+// settings of an instance, saved in instances/<name>/instance.json
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Instance {
+    pub name: String,
+    // id of the version of the game (1.21, 1.8.9...)
+    pub version: String,
+    pub loader: Loader,
+    // java executable to use instead of the runtime installed by the Updater
+    pub java_path: Option<String>,
+    // memory given to the game, in MB (-Xms / -Xmx)
+    pub min_memory: Option<u32>,
+    pub max_memory: Option<u32>,
+    pub jvm_args: Vec<String>,
+    pub game_args: Vec<String>,
+    pub resolution: Option<Resolution>,
+    // path or url of the icon, the library doesn't read it
+    pub icon: Option<String>,
+    // unix timestamp (in seconds) of the last launch
+    pub last_played: Option<u64>,
+    // total time played, in seconds
+    pub play_time: u64,
+}
+
+impl Instance {
+    pub fn new<S: ToString>(name: S, version: S) -> Self {
+        Self {
+            name: name.to_string(),
+            version: version.to_string(),
+            ..Self::default()
+        }
+    }
+
+    // memory settings first, then the jvm arguments of the instance
+    pub fn all_jvm_args(&self) -> Vec<String> {
+        self.min_memory
+            .map(|memory| format!("-Xms{}M", memory))
+            .into_iter()
+            .chain(self.max_memory.map(|memory| format!("-Xmx{}M", memory)))
+            .chain(self.jvm_args.iter().cloned())
+            .collect()
+    }
+}
+
+// create, list, modify and launch the instances of a launcher directory (in its instances/ dir)
+#[derive(Clone)]
+pub struct InstanceManager {
+    local_dir_path: String,
+    // given to the updaters and launchers of the instances
+    client: Client,
+}
+
+impl InstanceManager {
+    pub fn new(local_dir_path: String) -> Self {
+        Self::with_client(local_dir_path, Client::new())
+    }
+
+    // create a manager that shares the http client of the launcher
+    pub fn with_client(local_dir_path: String, client: Client) -> Self {
+        Self { local_dir_path, client }
+    }
+
+    pub fn set_client(&mut self, client: Client) {
+        self.client = client;
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    pub fn set_relative_local_dir_path(&mut self, local_dir_path: &str) {
+        self.local_dir_path = get_relative_local_dir_path(local_dir_path);
+    }
+
+    pub fn local_dir_path(&self) -> &str {
+        &self.local_dir_path
+    }
+
+    // dir the game of the instance runs in, with its saves, options, mods...
+    pub fn game_dir(&self, name: &str) -> String {
+        self.local_dir_path.clone()
+            + INSTANCES_DIR
+            + std::path::MAIN_SEPARATOR_STR
+            + name
+            + std::path::MAIN_SEPARATOR_STR
+    }
+
+    fn instance_file(&self, name: &str) -> String {
+        self.game_dir(name) + INSTANCE_FILE
+    }
+
+    pub fn exists(&self, name: &str) -> bool {
+        Path::new(&self.instance_file(name)).exists()
+    }
+
+    pub fn create(&self, instance: &Instance) -> Result<()> {
+        check_instance_name(&instance.name)?;
+        if self.exists(&instance.name) {
+            return Err(LauncherError::InvalidInstance(format!("{} already exists", instance.name)));
+        }
+        self.save(instance)
+    }
+
+    // write the settings of the instance to its instance.json
+    pub fn save(&self, instance: &Instance) -> Result<()> {
+        check_instance_name(&instance.name)?;
+        let game_dir = self.game_dir(&instance.name);
+        fs::create_dir_all(&game_dir).map_err(|e| LauncherError::io(&game_dir, e))?;
+
+        let instance_file = self.instance_file(&instance.name);
+        let json = serde_json::to_string_pretty(instance)
+            .map_err(|e| LauncherError::manifest(&instance_file, e))?;
+        fs::write(&instance_file, json).map_err(|e| LauncherError::io(&instance_file, e))
+    }
+
+    pub fn get(&self, name: &str) -> Result<Instance> {
+        check_instance_name(name)?;
+        let instance_file = self.instance_file(name);
+        let json = match fs::read_to_string(&instance_file) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(LauncherError::InstanceNotFound(name.to_string()))
+            }
+            Err(e) => return Err(LauncherError::io(&instance_file, e)),
+        };
+        serde_json::from_str(&json).map_err(|e| LauncherError::manifest(&instance_file, e))
+    }
+
+    // every instance of the launcher dir, sorted by name
+    // the dirs without a readable instance.json are ignored
+    pub fn list(&self) -> Result<Vec<Instance>> {
+        let instances_dir = self.local_dir_path.clone() + INSTANCES_DIR;
+        let entries = match fs::read_dir(&instances_dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(LauncherError::io(&instances_dir, e)),
+        };
+
+        let mut instances: Vec<Instance> = entries
+            .flatten()
+            .filter_map(|entry| self.get(&entry.file_name().to_string_lossy()).ok())
+            .collect();
+        instances.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(instances)
+    }
+
+    // rename the instance, its dir is moved with it
+    pub fn rename(&self, name: &str, new_name: &str) -> Result<Instance> {
+        check_instance_name(new_name)?;
+        let mut instance = self.get(name)?;
+        if self.exists(new_name) {
+            return Err(LauncherError::InvalidInstance(format!("{} already exists", new_name)));
+        }
+
+        let game_dir = self.game_dir(name);
+        fs::rename(&game_dir, self.game_dir(new_name)).map_err(|e| LauncherError::io(&game_dir, e))?;
+        instance.name = new_name.to_string();
+        self.save(&instance)?;
+        Ok(instance)
+    }
+
+    // copy the instance with all its files (saves, options, mods...), without its play stats
+    pub fn duplicate(&self, name: &str, new_name: &str) -> Result<Instance> {
+        check_instance_name(new_name)?;
+        let mut instance = self.get(name)?;
+        if self.exists(new_name) {
+            return Err(LauncherError::InvalidInstance(format!("{} already exists", new_name)));
+        }
+
+        copy_dir_recursive(Path::new(&self.game_dir(name)), Path::new(&self.game_dir(new_name)))?;
+        instance.name = new_name.to_string();
+        instance.last_played = None;
+        instance.play_time = 0;
+        self.save(&instance)?;
+        Ok(instance)
+    }
+
+    // remove the instance and all its files
+    pub fn delete(&self, name: &str) -> Result<()> {
+        check_instance_name(name)?;
+        if !self.exists(name) {
+            return Err(LauncherError::InstanceNotFound(name.to_string()));
+        }
+        let game_dir = self.game_dir(name);
+        fs::remove_dir_all(&game_dir).map_err(|e| LauncherError::io(&game_dir, e))
+    }
+
+    // updater installing the version of the instance in the shared dirs of the launcher
+    pub fn updater(&self, instance: &Instance) -> Result<Updater> {
+        check_loader(instance)?;
+        let mut updater = Updater::new(&instance.version);
+        updater.set_local_dir_path(self.local_dir_path.clone());
+        updater.set_client(self.client.clone());
+        Ok(updater)
+    }
+
+    // launcher running the game of the instance in its own dir, with its settings
+    pub fn launcher(&self, instance: &Instance) -> Result<GameLauncher> {
+        check_loader(instance)?;
+        let mut launcher = GameLauncher::with_local_dir_path(
+            instance.version.clone(),
+            self.local_dir_path.clone(),
+            instance.game_args.clone(),
+            instance.all_jvm_args(),
+        );
+        launcher.set_client(self.client.clone());
+        launcher.set_game_dir(self.game_dir(&instance.name));
+        launcher.set_java_path(instance.java_path.clone());
+        if let Some(resolution) = instance.resolution {
+            launcher.set_resolution(resolution.width, resolution.height);
+        }
+        Ok(launcher)
+    }
+
+    // launch the game of the instance, and update its last played date
+    // the time the game ran is added to its play time once it exits
+    pub async fn launch(&self, name: &str, session: &Session) -> Result<GameProcess> {
+        let mut instance = run_blocking({
+            let (manager, name) = (self.clone(), name.to_string());
+            move || manager.get(&name)
+        })
+        .await?;
+        let launcher = self.launcher(&instance)?;
+        instance.last_played = Some(unix_timestamp());
+        run_blocking({
+            let manager = self.clone();
+            move || manager.save(&instance)
+        })
+        .await?;
+
+        let mut game_process = launcher.launch(session).await?;
+        let (manager, name) = (self.clone(), name.to_string());
+        game_process.set_on_exit(Box::new(move |play_time| {
+            let _ = manager.add_play_time(&name, play_time);
+        }));
+        Ok(game_process)
+    }
+
+    pub fn add_play_time(&self, name: &str, play_time: Duration) -> Result<Instance> {
+        let mut instance = self.get(name)?;
+        instance.play_time += play_time.as_secs();
+        self.save(&instance)?;
+        Ok(instance)
+    }
+}
+
+// only the vanilla game can be installed and launched for now, the instances of a mod loader would run without it
+fn check_loader(instance: &Instance) -> Result<()> {
+    match instance.loader {
+        Loader::Vanilla => Ok(()),
+        loader => Err(LauncherError::InvalidInstance(format!(
+            "the {:?} loader of {} is not supported yet",
+            loader, instance.name
+        ))),
+    }
+}
+
+// the name is used as the name of the dir of the instance
+fn check_instance_name(name: &str) -> Result<()> {
+    let is_valid = !name.trim().is_empty()
+        && name != "."
+        && name != ".."
+        && !name.contains(['/', '\\', ':', '*', '?', '"', '<', '>', '|']);
+    if is_valid {
+        Ok(())
+    } else {
+        Err(LauncherError::InvalidInstance(format!("invalid name: {:?}", name)))
+    }
+}
+
+fn copy_dir_recursive(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to).map_err(|e| LauncherError::io(to.display(), e))?;
+    for entry in fs::read_dir(from).map_err(|e| LauncherError::io(from.display(), e))? {
+        let entry = entry.map_err(|e| LauncherError::io(from.display(), e))?;
+        let path = entry.path();
+        let target = to.join(entry.file_name());
+        if path.is_dir() {
+            copy_dir_recursive(&path, &target)?;
+        } else {
+            fs::copy(&path, &target).map_err(|e| LauncherError::io(path.display(), e))?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use crate::update::structs::mc_libs::LibsRoot;
+use crate::update::structs::rules::{allowed_arguments, Environment};
+
+// jvm arguments of the versions before 1.13, that don't have them in their json
+const LEGACY_JVM_ARGUMENTS: [&str; 3] = ["-Djava.library.path=${natives_directory}", "-cp", "${classpath}"];
+
+// values of the ${...} placeholders of the arguments (auth_player_name, classpath, natives_directory...)
+pub(crate) type ArgumentsVariables = HashMap<&'static str, String>;
+
+// jvm arguments of the version allowed in the environment, with the placeholders replaced
+pub(crate) fn jvm_arguments(
+    libs_manifest: &LibsRoot,
+    environment: &Environment,
+    variables: &ArgumentsVariables,
+) -> Vec<String> {
+    match &libs_manifest.arguments {
+        Some(arguments) if !arguments.jvm.is_empty() => {
+            expand_arguments(&allowed_arguments(&arguments.jvm, environment), variables)
+        }
+        _ => expand_arguments(&LEGACY_JVM_ARGUMENTS, variables),
+    }
+}
+
+// game arguments of the version allowed in the environment, with the placeholders replaced
+// the old versions only have a single line (minecraftArguments), split on the spaces
+pub(crate) fn game_arguments(
+    libs_manifest: &LibsRoot,
+    environment: &Environment,
+    variables: &ArgumentsVariables,
+) -> Vec<String> {
+    match (&libs_manifest.arguments, &libs_manifest.minecraft_arguments) {
+        (Some(arguments), _) if !arguments.game.is_empty() => {
+            expand_arguments(&allowed_arguments(&arguments.game, environment), variables)
+        }
+        (_, Some(minecraft_arguments)) => {
+            let arguments: Vec<&str> = minecraft_arguments.split_whitespace().collect();
+            expand_arguments(&arguments, variables)
+        }
+        _ => Vec::new(),
+    }
+}
+
+pub(crate) fn expand_arguments(arguments: &[&str], variables: &ArgumentsVariables) -> Vec<String> {
+    arguments
+        .iter()
+        .map(|argument| expand_argument(argument, variables))
+        .collect()
+}
+
+// replace the ${...} placeholders of the argument, the unknown ones are kept as they are
+pub(crate) fn expand_argument(argument: &str, variables: &ArgumentsVariables) -> String {
+    let mut expanded = String::with_capacity(argument.len());
+    let mut rest = argument;
+
+    while let Some(start) = rest.find("${") {
+        expanded.push_str(&rest[..start]);
+        let placeholder = &rest[start..];
+        match placeholder.find('}') {
+            Some(end) => {
+                match variables.get(&placeholder[2..end]) {
+                    Some(value) => expanded.push_str(value),
+                    None => expanded.push_str(&placeholder[..=end]),
+                }
+                rest = &placeholder[end + 1..];
+            }
+            None => {
+                expanded.push_str(placeholder);
+                rest = "";
+            }
+        }
+    }
+
+    expanded.push_str(rest);
+    expanded
+}
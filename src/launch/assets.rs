@@ -0,0 +1,57 @@
+use std::fs;
+use std::path::Path;
+
+use crate::error::{LauncherError, Result};
+use crate::update::structs::mc_assets::AssetsRoot;
+use crate::update::utils::{get_asset_index_path, get_asset_path_from_hash, Directory};
+
+// dir of the assets given to the game (game_assets), the old versions read them by name instead of by hash :
+// the objects are copied to assets/virtual/<index>/ (1.6 to 1.7.2) or to <game dir>/resources/ (before 1.6)
+pub(crate) fn prepare_assets(
+    local_dir_path: &str,
+    game_dir: &str,
+    asset_index_id: &str,
+    asset_index_url: &str,
+) -> Result<String> {
+    let assets_dir = local_dir_path.to_string() + &Directory::Assets.as_str();
+    // without its index (the version isn't installed), the assets can't be laid out
+    let index_path = get_asset_index_path(local_dir_path, asset_index_url);
+    let Some(assets) = fs::read_to_string(&index_path)
+        .ok()
+        .and_then(|json| serde_json::from_str::<AssetsRoot>(&json).ok())
+    else {
+        return Ok(assets_dir);
+    };
+
+    let legacy_dir = if assets.map_to_resources() {
+        Path::new(game_dir).join("resources")
+    } else if assets.is_virtual() {
+        Path::new(&assets_dir).join("virtual").join(asset_index_id)
+    } else {
+        return Ok(assets_dir);
+    };
+    copy_assets_by_name(local_dir_path, &assets, &legacy_dir)?;
+    Ok(legacy_dir.to_string_lossy().to_string() + std::path::MAIN_SEPARATOR_STR)
+}
+
+// copy each object to its name, the files already copied (with the same size) are kept
+fn copy_assets_by_name(local_dir_path: &str, assets: &AssetsRoot, legacy_dir: &Path) -> Result<()> {
+    for (name, object) in assets.objects() {
+        let target = legacy_dir.join(name);
+        // the names come from the index, they must stay in the dir
+        if !target.starts_with(legacy_dir) || name.split(['/', '\\']).any(|part| part == "..") {
+            continue;
+        }
+        let is_copied = fs::metadata(&target).is_ok_and(|metadata| metadata.len() == object.size() as u64);
+        let (_, object_path) = get_asset_path_from_hash(local_dir_path.to_string(), object.hash());
+        if is_copied || !Path::new(&object_path).exists() {
+            continue;
+        }
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| LauncherError::io(parent.display(), e))?;
+        }
+        fs::copy(&object_path, &target).map_err(|e| LauncherError::io(target.display(), e))?;
+    }
+    Ok(())
+}
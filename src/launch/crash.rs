@@ -0,0 +1,196 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// ------------------------------------------------------------------------------- //
+// Crash reports of the game (crash-reports/*.txt) and of the jvm (hs_err_pid*.log) //
+// ------------------------------------------------------------------------------- //
+
+const CRASH_REPORTS_DIR: &str = "crash-reports";
+const JVM_REPORT_PREFIX: &str = "hs_err_pid";
+
+// keys of the system details that list the mods, depending on the loader
+const MOD_LIST_KEYS: [&str; 4] = ["Mod List", "Fabric Mods", "Quilt Mods", "Loaded mods"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrashKind {
+    // an exception in the game, written by Minecraft
+    Game,
+    // a fatal error of the jvm (in a native lib, out of memory...)
+    Jvm,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrashReport {
+    pub path: String,
+    pub kind: CrashKind,
+    pub description: String,
+    // exception of the crash (class: message), or the problematic frame for the jvm crashes
+    pub exception: String,
+    pub stack_trace: Vec<String>,
+    // mods listed in the report, with their version as written by the loader
+    pub mods: Vec<String>,
+    // key / value of the system details, in the order of the report
+    pub system_details: Vec<(String, String)>,
+}
+
+impl CrashReport {
+    // read and parse a crash report, its kind is found from its name
+    pub fn from_file(path: &Path) -> Option<Self> {
+        let text = fs::read_to_string(path).ok()?;
+        let path_str = path.to_string_lossy().to_string();
+        let is_jvm_report = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with(JVM_REPORT_PREFIX));
+
+        if is_jvm_report {
+            Some(Self::parse_jvm_report(path_str, &text))
+        } else {
+            Some(Self::parse_game_report(path_str, &text))
+        }
+    }
+
+    pub fn parse_game_report(path: String, text: &str) -> Self {
+        let lines: Vec<&str> = text.lines().collect();
+        let description = lines
+            .iter()
+            .find_map(|line| line.strip_prefix("Description:"))
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+
+        // the exception comes after the description, until the first empty line
+        let mut trace = lines
+            .iter()
+            .skip_while(|line| !line.starts_with("Description:"))
+            .skip(1)
+            .skip_while(|line| line.trim().is_empty())
+            .take_while(|line| !line.trim().is_empty())
+            .map(|line| line.trim().to_string());
+        let exception = trace.next().unwrap_or_default();
+        let stack_trace = trace.collect();
+
+        let (system_details, mods) = parse_system_details(&lines);
+        Self {
+            path,
+            kind: CrashKind::Game,
+            description,
+            exception,
+            stack_trace,
+            mods,
+            system_details,
+        }
+    }
+
+    pub fn parse_jvm_report(path: String, text: &str) -> Self {
+        let header: Vec<&str> = text
+            .lines()
+            .take_while(|line| line.starts_with('#'))
+            .map(|line| line.trim_start_matches('#').trim())
+            .collect();
+
+        let description = header
+            .iter()
+            .skip_while(|line| !line.starts_with("A fatal error"))
+            .skip(1)
+            .find(|line| !line.is_empty())
+            .unwrap_or(&"")
+            .to_string();
+        let exception = header
+            .iter()
+            .skip_while(|line| !line.starts_with("Problematic frame:"))
+            .nth(1)
+            .unwrap_or(&"")
+            .to_string();
+        let system_details = header
+            .iter()
+            .filter_map(|line| line.split_once(": "))
+            .filter(|(key, _value)| key.starts_with("JRE version") || key.starts_with("Java VM"))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        let stack_trace = text
+            .lines()
+            .skip_while(|line| !line.starts_with("Native frames:") && !line.starts_with("Java frames:"))
+            .skip(1)
+            .take_while(|line| !line.trim().is_empty())
+            .map(|line| line.to_string())
+            .collect();
+
+        Self {
+            path,
+            kind: CrashKind::Jvm,
+            description,
+            exception,
+            stack_trace,
+            mods: vec![],
+            system_details,
+        }
+    }
+}
+
+// the details are the lines "\tKey: Value" of the System Details section,
+// the mods are the lines under the key of the mod list (indented once more)
+fn parse_system_details(lines: &[&str]) -> (Vec<(String, String)>, Vec<String>) {
+    let mut system_details = vec![];
+    let mut mods = vec![];
+    let mut in_mod_list = false;
+
+    let details = lines
+        .iter()
+        .skip_while(|line| !line.starts_with("-- System Details --"))
+        .skip(1);
+    for line in details {
+        if line.starts_with("\t\t") || line.starts_with("        ") {
+            if in_mod_list && !line.trim().is_empty() {
+                mods.push(line.trim().to_string());
+            }
+            continue;
+        }
+        if let Some((key, value)) = line.trim().split_once(':') {
+            let key = key.trim();
+            in_mod_list = MOD_LIST_KEYS.contains(&key);
+            if key != "Details" {
+                system_details.push((key.to_string(), value.trim().to_string()));
+            }
+        }
+    }
+
+    (system_details, mods)
+}
+
+// crash report of a game that crashed : the one it printed, else the newest one written since the launch
+// in crash-reports/, else the report of the jvm (hs_err_pid<pid>.log) in the game dir
+pub(crate) fn find_crash_report(
+    game_dir: &str,
+    printed_path: Option<&str>,
+    pid: Option<u32>,
+    started_at: SystemTime,
+) -> Option<CrashReport> {
+    if let Some(report) = printed_path.and_then(|path| CrashReport::from_file(Path::new(path))) {
+        return Some(report);
+    }
+
+    let game_dir = Path::new(game_dir);
+    if let Some(path) = newest_file_since(&game_dir.join(CRASH_REPORTS_DIR), started_at, |_name| true) {
+        return CrashReport::from_file(&path);
+    }
+
+    let jvm_report = pid
+        .map(|pid| game_dir.join(format!("{}{}.log", JVM_REPORT_PREFIX, pid)))
+        .filter(|path| path.exists())
+        .or_else(|| newest_file_since(game_dir, started_at, |name| name.starts_with(JVM_REPORT_PREFIX)))?;
+    CrashReport::from_file(&jvm_report)
+}
+
+fn newest_file_since(dir: &Path, since: SystemTime, filter: impl Fn(&str) -> bool) -> Option<PathBuf> {
+    fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter(|entry| filter(&entry.file_name().to_string_lossy()))
+        .filter_map(|entry| {
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            (modified >= since).then(|| (modified, entry.path()))
+        })
+        .max_by_key(|(modified, _path)| *modified)
+        .map(|(_modified, path)| path)
+}
@@ -0,0 +1,149 @@
+// ------------------------------------------------------------------------------------ //
+// Parsing of the logs of the game, printed as log4j xml events with the config of the //
+// version json (logging.client), or as plain lines by the old versions                //
+// ------------------------------------------------------------------------------------ //
+
+const EVENT_START: &str = "<log4j:Event";
+const EVENT_END: &str = "</log4j:Event>";
+// an event that is still not closed at this size is given as a plain line (it can't be log4j)
+const MAX_EVENT_LENGTH: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl LogLevel {
+    // the unknown levels are read as info
+    fn parse(level: &str) -> Self {
+        match level.to_ascii_uppercase().as_str() {
+            "TRACE" => LogLevel::Trace,
+            "DEBUG" => LogLevel::Debug,
+            "WARN" => LogLevel::Warn,
+            "ERROR" => LogLevel::Error,
+            "FATAL" => LogLevel::Fatal,
+            _ => LogLevel::Info,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEvent {
+    pub level: LogLevel,
+    pub thread: String,
+    pub logger: String,
+    // unix timestamp in milliseconds, None for the plain lines
+    pub timestamp: Option<u64>,
+    pub message: String,
+    // stack trace of the exception logged with the message
+    pub throwable: Option<String>,
+}
+
+impl LogEvent {
+    // a line printed outside of log4j (System.out, old versions...)
+    fn plain(line: &str) -> Self {
+        Self {
+            level: LogLevel::Info,
+            thread: String::new(),
+            logger: String::new(),
+            timestamp: None,
+            message: line.to_string(),
+            throwable: None,
+        }
+    }
+}
+
+// gather the lines of the output until they make a whole event
+#[derive(Default)]
+pub(crate) struct LogParser {
+    event: Option<String>,
+}
+
+impl LogParser {
+    // give the next line of the output, returns an event once it's complete
+    pub(crate) fn push_line(&mut self, line: &str) -> Option<LogEvent> {
+        match &mut self.event {
+            Some(event) => {
+                event.push('\n');
+                event.push_str(line);
+            }
+            None if line.trim_start().starts_with(EVENT_START) => self.event = Some(line.to_string()),
+            None if line.trim().is_empty() => return None,
+            None => return Some(LogEvent::plain(line)),
+        }
+
+        if line.contains(EVENT_END) {
+            let event = self.event.take()?;
+            return Some(parse_event(&event).unwrap_or_else(|| LogEvent::plain(&event)));
+        }
+        if self.event.as_ref().is_some_and(|event| event.len() > MAX_EVENT_LENGTH) {
+            return self.event.take().map(|event| LogEvent::plain(&event));
+        }
+        None
+    }
+}
+
+fn parse_event(event: &str) -> Option<LogEvent> {
+    let start = event.find(EVENT_START)? + EVENT_START.len();
+    let tag_end = start + event[start..].find('>')?;
+    let attributes = &event[start..tag_end];
+
+    Some(LogEvent {
+        level: LogLevel::parse(&attribute(attributes, "level").unwrap_or_default()),
+        thread: attribute(attributes, "thread").unwrap_or_default(),
+        logger: attribute(attributes, "logger").unwrap_or_default(),
+        timestamp: attribute(attributes, "timestamp").and_then(|timestamp| timestamp.parse().ok()),
+        message: element_text(event, "log4j:Message").unwrap_or_default(),
+        throwable: element_text(event, "log4j:Throwable"),
+    })
+}
+
+// value of an attribute of the tag (name="value")
+fn attribute(attributes: &str, name: &str) -> Option<String> {
+    let pattern = format!("{}=\"", name);
+    let mut search_from = 0;
+    while let Some(position) = attributes[search_from..].find(&pattern) {
+        let start = search_from + position;
+        let value_start = start + pattern.len();
+        // "thread" must not match in "mythread"
+        let is_whole_name = attributes[..start]
+            .chars()
+            .last()
+            .is_none_or(|c| c.is_whitespace());
+        if is_whole_name {
+            let value_end = value_start + attributes[value_start..].find('"')?;
+            return Some(unescape(&attributes[value_start..value_end]));
+        }
+        search_from = value_start;
+    }
+    None
+}
+
+// text of an element of the event, in a CDATA section or escaped
+fn element_text(event: &str, name: &str) -> Option<String> {
+    let open_tag = format!("<{}>", name);
+    let close_tag = format!("</{}>", name);
+    let start = event.find(&open_tag)? + open_tag.len();
+    let end = start + event[start..].find(&close_tag)?;
+    let text = event[start..end].trim();
+
+    match text.strip_prefix("<![CDATA[").and_then(|text| text.strip_suffix("]]>")) {
+        Some(cdata) => Some(cdata.to_string()),
+        None => Some(unescape(text)),
+    }
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#10;", "\n")
+        .replace("&#13;", "\r")
+        .replace("&amp;", "&")
+}
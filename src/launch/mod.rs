@@ -1,98 +1,242 @@
-use std::env::consts::OS;
-use std::error::Error;
-use std::path::MAIN_SEPARATOR_STR;
-use std::process::Command;
+use std::collections::HashMap;
+use std::process::Stdio;
 
-use crate::launch::utils::LaunchBuilder;
+use reqwest::Client;
+use tokio::process::Command;
+
+use crate::auth::session::Session;
+use crate::error::{LauncherError, Result};
+use crate::launch::arguments::{game_arguments, jvm_arguments, ArgumentsVariables};
+use crate::launch::assets::prepare_assets;
+use crate::launch::natives::extract_natives;
+use crate::launch::process::GameProcess;
+use crate::launch::utils::{LaunchBuilder, CLASSPATH_SEPARATOR};
+use crate::update::structs::mc_libs::LibsRoot;
+use crate::update::structs::rules::{Environment, FEATURE_CUSTOM_RESOLUTION};
 use crate::update::updater::Updater;
-use crate::update::utils::{Directory, get_relative_local_dir_path};
+use crate::update::utils::{
+    get_client_jar_path, get_java_path, get_log_config_path, get_relative_local_dir_path, get_version_json_path, run_blocking,
+    Directory,
+};
 
-mod utils;
+pub(crate) mod arguments;
+pub(crate) mod assets;
+pub mod crash;
+pub mod logs;
+pub(crate) mod natives;
+pub mod process;
+pub(crate) mod utils;
 
-pub(crate) struct GameLauncher {
+pub struct GameLauncher {
     version: String,
+    // root of the launcher, with the versions, libraries, assets and runtimes shared by the instances
+    local_dir_path: String,
+    // dir the game runs in (saves, options, mods...), the root of the launcher by default
     game_dir: String,
     game_args: Vec<String>,
     jvm_args: Vec<String>,
+    environment: Environment,
+    resolution: Option<(u32, u32)>,
+    // java executable to use instead of the runtime installed by the Updater
+    java_path: Option<String>,
+    // used to get the json of the version when it's not installed
+    client: Client,
 }
 
 impl GameLauncher {
     pub fn new(
         version: String,
-        game_dir: String,
+        local_dir_path: String,
+        game_args: Vec<String>,
+        jvm_args: Vec<String>,
+    ) -> Self {
+        let local_dir_path = get_relative_local_dir_path(local_dir_path.as_str());
+        Self::with_local_dir_path(version, local_dir_path, game_args, jvm_args)
+    }
+
+    // same as new, with the full path of the root of the launcher instead of a dir in the home / appdata dir
+    pub fn with_local_dir_path(
+        version: String,
+        local_dir_path: String,
         game_args: Vec<String>,
         jvm_args: Vec<String>,
     ) -> Self {
         Self {
             version,
-            game_dir: get_relative_local_dir_path(game_dir.as_str()),
+            game_dir: local_dir_path.clone(),
+            local_dir_path,
             game_args,
             jvm_args,
+            environment: Environment::current(),
+            resolution: None,
+            java_path: None,
+            client: Client::new(),
         }
     }
 
-    //launch the game using the access_token / pseudo
-    pub fn launch(&self, access_token: &str, username: &str) -> Result<(), Box<dyn Error>> {
-        let mut updater = Updater::new(&self.version);
-        if updater.update_files_list().is_err() {
-            return Err("Error getting files list".into());
-        }
+    // use the given http client (shared with the rest of the launcher) for the requests of the launcher
+    pub fn set_client(&mut self, client: Client) {
+        self.client = client;
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    // run the game in its own dir, so the instances of the launcher don't share their saves and options
+    pub fn set_game_dir(&mut self, game_dir: String) {
+        self.game_dir = game_dir;
+    }
+
+    pub fn set_local_dir_path(&mut self, local_dir_path: String) {
+        self.local_dir_path = local_dir_path;
+    }
+
+    pub fn set_java_path(&mut self, java_path: Option<String>) {
+        self.java_path = java_path;
+    }
+
+    pub fn game_dir(&self) -> &str {
+        &self.game_dir
+    }
+
+    pub fn local_dir_path(&self) -> &str {
+        &self.local_dir_path
+    }
+
+    // size of the window of the game, passed with the has_custom_resolution feature
+    pub fn set_resolution(&mut self, width: u32, height: u32) {
+        self.resolution = Some((width, height));
+        self.environment.set_feature(FEATURE_CUSTOM_RESOLUTION, true);
+    }
 
-        let extension = if cfg!(windows) { ".exe" } else { "" };
+    // os and features the rules of the libs and arguments are evaluated against
+    pub fn set_environment(&mut self, environment: Environment) {
+        self.environment = environment;
+    }
+
+    pub fn environment(&self) -> &Environment {
+        &self.environment
+    }
 
-        let mut builder: LaunchBuilder = LaunchBuilder::new(format!(
-            "{}{}bin{}java{}",
-            self.game_dir.clone(),
-            Directory::Runtime.as_str(),
-            MAIN_SEPARATOR_STR,
-            extension
-        ));
+    //launch the game with the session of an account (see AccountManager::session), the returned handle gives its output and its exit status
+    pub async fn launch(&self, session: &Session) -> Result<GameProcess> {
+        let libs_manifest = &self.load_version_json().await?;
+        let java_version = libs_manifest.java_version.major_version.to_string();
+
+        let java_path = self
+            .java_path
+            .clone()
+            .unwrap_or_else(|| get_java_path(&self.local_dir_path, &java_version));
+        let mut builder: LaunchBuilder = LaunchBuilder::new(java_path);
         builder.set_libs_to_launch(
-            format!("{}{}", &self.game_dir, &Directory::Libraries.as_str()),
-            format!("{}client.jar", &self.game_dir),
+            &self.local_dir_path,
+            &libs_manifest.libraries,
+            &self.environment,
+            get_client_jar_path(&self.local_dir_path, &libs_manifest.id),
         );
 
-        println!("{}", builder.libs());
-        // Ok(())
+        if !std::path::Path::new(builder.program()).exists() {
+            return Err(LauncherError::JavaRuntimeMissing(builder.program().to_string()));
+        }
 
-        let mut command = Command::new(
-            self.game_dir.clone()
-                + &Directory::Runtime.as_str()
-                + "bin"
-                + &*std::path::MAIN_SEPARATOR.to_string()
-                + "java"
-                + extension,
-        );
+        let natives_dir = run_blocking({
+            let (local_dir_path, version) = (self.local_dir_path.clone(), self.version.clone());
+            let (libraries, environment) = (libs_manifest.libraries.clone(), self.environment.clone());
+            move || extract_natives(&local_dir_path, &version, &libraries, &environment)
+        })
+        .await?;
+        let game_assets = run_blocking({
+            let (local_dir_path, game_dir) = (self.local_dir_path.clone(), self.game_dir.clone());
+            let asset_index = libs_manifest.asset_index.clone();
+            move || {
+                let game_assets = prepare_assets(&local_dir_path, &game_dir, &asset_index.id, &asset_index.url)?;
+                std::fs::create_dir_all(&game_dir).map_err(|e| LauncherError::io(&game_dir, e))?;
+                Ok(game_assets)
+            }
+        })
+        .await?;
+        let variables = self.arguments_variables(libs_manifest, session, natives_dir.path(), &game_assets, builder.libs());
+
+        let mut command = Command::new(builder.program());
         command.args(&self.jvm_args);
-        command.arg("-cp");
-        command.arg(builder.libs());
-        command.arg("net.minecraft.client.main.Main");
-        command.args(["--accessToken", access_token]);
-        command.args(["--version", &*self.version]);
-        command.args(["--username", username]);
-        command.args(["--gameDir", &*self.game_dir]);
-        command.args([
-            "--assetIndex",
-            updater
-                .libs_manifest()
-                .as_ref()
-                .unwrap()
-                .asset_index
-                .id
-                .as_str(),
-        ]);
-        command.args([
-            "--assetsDir",
-            &(self.game_dir.clone() + &*Directory::Assets.as_str()),
-        ]);
-
-        println!("Launching game with command: {:?}", command);
-
-        // stream retour à retourner
-        let output = command.output()?;
-        println!("{}", String::from_utf8_lossy(&output.stdout));
-        println!("{}", String::from_utf8_lossy(&output.stderr));
-
-        Ok(())
+        command.args(jvm_arguments(libs_manifest, &self.environment, &variables));
+        // the game prints its logs as log4j xml events, parsed by the GameProcess
+        if let Some(logging) = libs_manifest.logging.as_ref().and_then(|logging| logging.client.as_ref()) {
+            let config_path = get_log_config_path(&self.local_dir_path, &logging.file.id);
+            if std::path::Path::new(&config_path).exists() {
+                command.arg(logging.argument.replace("${path}", &config_path));
+            }
+        }
+        command.arg(&libs_manifest.main_class);
+        command.args(game_arguments(libs_manifest, &self.environment, &variables));
+        command.args(&self.game_args);
+        command.current_dir(&self.game_dir);
+
+        command.stdin(Stdio::null());
+        command.stdout(Stdio::piped());
+        command.stderr(Stdio::piped());
+
+        let child = command
+            .spawn()
+            .map_err(|e| LauncherError::io(builder.program(), e))?;
+        let mut game_process = GameProcess::new(child, self.game_dir.clone());
+        game_process.set_natives_dir(natives_dir);
+        Ok(game_process)
+    }
+
+    // json of the version saved by the Updater, or from the Mojang api when the version isn't installed yet
+    async fn load_version_json(&self) -> Result<LibsRoot> {
+        let json_path = get_version_json_path(&self.local_dir_path, &self.version);
+        if let Ok(json) = tokio::fs::read_to_string(&json_path).await {
+            return LibsRoot::parse_json(json);
+        }
+
+        let mut updater = Updater::new(&self.version);
+        updater.set_client(self.client.clone());
+        updater.update_files_list().await?;
+        updater
+            .libs_manifest()
+            .clone()
+            .ok_or_else(|| LauncherError::VersionNotFound(self.version.clone()))
+    }
+
+    // values of the placeholders of the arguments of the version json
+    fn arguments_variables(
+        &self,
+        libs_manifest: &LibsRoot,
+        session: &Session,
+        natives_dir: &str,
+        game_assets: &str,
+        classpath: &str,
+    ) -> ArgumentsVariables {
+        let assets_dir = self.local_dir_path.clone() + &Directory::Assets.as_str();
+        let (width, height) = self.resolution.unwrap_or((854, 480));
+
+        HashMap::from([
+            ("auth_player_name", session.username.clone()),
+            ("auth_access_token", session.access_token.clone()),
+            ("auth_uuid", session.uuid.clone()),
+            ("auth_session", format!("token:{}:{}", session.access_token, session.uuid)),
+            ("auth_xuid", session.xuid.clone()),
+            ("clientid", session.client_id.clone()),
+            ("user_type", session.user_type.clone()),
+            ("user_properties", "{}".to_string()),
+            ("version_name", self.version.clone()),
+            ("version_type", libs_manifest.r#type.clone()),
+            ("game_directory", self.game_dir.clone()),
+            ("assets_root", assets_dir),
+            // assets by name of the versions before 1.7.3 (see prepare_assets)
+            ("game_assets", game_assets.to_string()),
+            ("assets_index_name", libs_manifest.asset_index.id.clone()),
+            ("natives_directory", natives_dir.to_string()),
+            ("library_directory", self.local_dir_path.clone() + &Directory::Libraries.as_str()),
+            ("classpath", classpath.to_string()),
+            ("classpath_separator", CLASSPATH_SEPARATOR.to_string()),
+            ("launcher_name", env!("CARGO_PKG_NAME").to_string()),
+            ("launcher_version", env!("CARGO_PKG_VERSION").to_string()),
+            ("resolution_width", width.to_string()),
+            ("resolution_height", height.to_string()),
+        ])
     }
 }
@@ -0,0 +1,144 @@
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::{LauncherError, Result};
+use crate::update::structs::mc_libs::Library;
+use crate::update::structs::rules::Environment;
+use crate::update::utils::{get_lib_path, Directory};
+
+// launches of this process, so two launches in the same millisecond don't share a directory
+static LAUNCH_COUNTER: AtomicU32 = AtomicU32::new(0);
+
+// natives directory of a launch, with the lock file (<dir>.lock) telling the other launches it's in use
+// the lock is released when it's dropped, the directory is then removed by the next launch
+pub(crate) struct NativesDir {
+    path: String,
+    lock: File,
+}
+
+impl NativesDir {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    // remove the directory once the game has exited
+    pub fn remove(self) {
+        let _ = fs::remove_dir_all(&self.path);
+        drop(self.lock);
+        let _ = fs::remove_file(lock_path(&self.path));
+    }
+}
+
+fn lock_path(natives_dir: &str) -> String {
+    format!("{}.lock", natives_dir)
+}
+
+// extract the natives of the libs allowed in the environment into a new directory, locked until the game exits
+// each launch gets its own directory, so a running game never has its natives replaced
+pub(crate) fn extract_natives(
+    local_dir_path: &str,
+    version: &str,
+    libraries: &[Library],
+    environment: &Environment,
+) -> Result<NativesDir> {
+    let natives_root = local_dir_path.to_string() + &Directory::Natives.as_str();
+    fs::create_dir_all(&natives_root).map_err(|e| LauncherError::io(&natives_root, e))?;
+
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or(0);
+    let natives_dir = format!(
+        "{}{}-{}-{}-{}",
+        natives_root,
+        version,
+        millis,
+        std::process::id(),
+        LAUNCH_COUNTER.fetch_add(1, Ordering::Relaxed)
+    );
+    // locked before the directory exists, so it's never seen unlocked by the cleaning of another launch
+    // the file is new, nobody else can hold its lock : try_lock doesn't wait
+    let lock_path = lock_path(&natives_dir);
+    let lock = File::create_new(&lock_path).map_err(|e| LauncherError::io(&lock_path, e))?;
+    lock.try_lock().map_err(|e| LauncherError::io(&lock_path, e.into()))?;
+    let natives_dir = NativesDir { path: natives_dir, lock };
+
+    clean_old_natives(&natives_root);
+    fs::create_dir_all(natives_dir.path()).map_err(|e| LauncherError::io(natives_dir.path(), e))?;
+
+    for library in libraries.iter().filter(|library| library.is_allowed(environment)) {
+        if let Some(artifact) = library.native_artifact(&environment.os_name) {
+            let jar_path = get_lib_path(local_dir_path, &artifact.path);
+            let exclude = library
+                .extract
+                .as_ref()
+                .map(|extract| extract.exclude.as_slice())
+                .unwrap_or_default();
+            extract_jar(&jar_path, natives_dir.path(), exclude)?;
+        }
+    }
+
+    Ok(natives_dir)
+}
+
+// remove the natives directories of the previous launches (of every version) that are not locked anymore
+// the ones of the games still running (in this launcher or another one) are kept
+fn clean_old_natives(natives_root: &str) {
+    let Ok(entries) = fs::read_dir(natives_root) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path().to_string_lossy().to_string();
+        // a lock file left without its directory is removed with it
+        let natives_dir = match path.strip_suffix(".lock") {
+            Some(natives_dir) => natives_dir.to_string(),
+            None if entry.path().is_dir() => path,
+            None => continue,
+        };
+
+        let lock_path = lock_path(&natives_dir);
+        // no lock file : left by an older version of the lib, nobody uses it
+        let lock = match File::open(&lock_path) {
+            Ok(lock) if lock.try_lock().is_err() => continue,
+            Ok(lock) => Some(lock),
+            Err(_e) => None,
+        };
+        let _ = fs::remove_dir_all(&natives_dir);
+        drop(lock);
+        let _ = fs::remove_file(lock_path);
+    }
+}
+
+// extract the files of a jar, except the directories and the entries starting with an excluded prefix
+fn extract_jar(jar_path: &str, natives_dir: &str, exclude: &[String]) -> Result<()> {
+    let jar = fs::File::open(jar_path).map_err(|e| LauncherError::io(jar_path, e))?;
+    let mut archive = zip::ZipArchive::new(jar)
+        .map_err(|e| LauncherError::io(jar_path, io::Error::new(io::ErrorKind::InvalidData, e)))?;
+
+    for index in 0..archive.len() {
+        let mut entry = archive
+            .by_index(index)
+            .map_err(|e| LauncherError::io(jar_path, io::Error::new(io::ErrorKind::InvalidData, e)))?;
+        let name = entry.name().to_string();
+        if entry.is_dir() || exclude.iter().any(|prefix| name.starts_with(prefix.as_str())) {
+            continue;
+        }
+        // enclosed_name refuses the entries going outside of the directory (../)
+        let relative_path = match entry.enclosed_name() {
+            Some(path) => path.to_path_buf(),
+            None => continue,
+        };
+
+        let output_path = Path::new(natives_dir).join(relative_path);
+        if let Some(parent) = output_path.parent() {
+            fs::create_dir_all(parent).map_err(|e| LauncherError::io(parent.display(), e))?;
+        }
+        let mut output = fs::File::create(&output_path)
+            .map_err(|e| LauncherError::io(output_path.display(), e))?;
+        io::copy(&mut entry, &mut output).map_err(|e| LauncherError::io(output_path.display(), e))?;
+    }
+    Ok(())
+}
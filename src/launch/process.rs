@@ -0,0 +1,359 @@
+use std::process::ExitStatus;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Child;
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+use tokio::task::JoinHandle;
+
+use crate::error::{LauncherError, Result};
+use crate::launch::crash::{find_crash_report, CrashReport};
+use crate::launch::logs::{LogEvent, LogParser};
+use crate::launch::natives::NativesDir;
+
+// line printed by the game (on stdout) when it crashes, followed by the path of the crash report
+const CRASH_MARKER: &str = "#@!@# Game crashed!";
+const CRASH_REPORT_MARKER: &str = "Crash report saved to: #@!@#";
+// lines of stdout / stderr kept until they are received, the next ones are dropped when it's full
+// (nobody took the receiver, or it's not read fast enough)
+const OUTPUT_BUFFER_LINES: usize = 10_000;
+// bytes kept of a line, the end of a longer one is dropped (a line without a newline mustn't fill the memory)
+pub(crate) const MAX_LINE_LENGTH: usize = 64 * 1024;
+
+// handle on a running game, returned by GameLauncher::launch
+// dropping it doesn't stop the game, its exit is then waited for in the background
+pub struct GameProcess {
+    // only taken by the drop, to wait for the game in the background
+    child: Option<Child>,
+    pid: Option<u32>,
+    stdout: Option<Receiver<String>>,
+    stderr: Option<Receiver<String>>,
+    // the logs are only parsed once the receiver of the events is taken
+    log_listener: Option<LogListener>,
+    crash: Arc<Mutex<CrashState>>,
+    // tasks reading the output, finished once the game closed it
+    output_tasks: Vec<JoinHandle<()>>,
+    exit_status: Option<ExitStatus>,
+    killed: bool,
+    started_at: Instant,
+    // the crash reports written before the launch are ignored
+    launched_at: SystemTime,
+    game_dir: String,
+    // removed when the game exits, it stays locked while the game runs
+    natives_dir: Option<NativesDir>,
+    on_exit: Option<OnExit>,
+}
+
+// how the game exited, returned by GameProcess::wait
+#[derive(Debug)]
+pub struct GameExit {
+    pub status: ExitStatus,
+    pub crashed: bool,
+    // report of the crash, when one was written by the game or the jvm
+    pub crash_report: Option<CrashReport>,
+}
+
+type LogListener = Arc<Mutex<Option<Sender<LogEvent>>>>;
+// called with the time the game ran, once it exited
+pub(crate) type OnExit = Box<dyn FnOnce(Duration) + Send>;
+
+#[derive(Default)]
+struct CrashState {
+    detected: bool,
+    report_path: Option<String>,
+}
+
+impl GameProcess {
+    // the stdout / stderr of the child must be piped
+    pub(crate) fn new(mut child: Child, game_dir: String) -> Self {
+        let crash = Arc::new(Mutex::new(CrashState::default()));
+        let mut output_tasks = vec![];
+
+        let log_listener = LogListener::default();
+        let stdout = child.stdout.take().map(|stdout| {
+            let (sender, receiver) = channel(OUTPUT_BUFFER_LINES);
+            let stdout_watcher = StdoutWatcher {
+                crash: crash.clone(),
+                log_parser: LogParser::default(),
+                log_listener: log_listener.clone(),
+            };
+            output_tasks.push(tokio::spawn(forward_lines(stdout, sender, Some(stdout_watcher))));
+            receiver
+        });
+        let stderr = child.stderr.take().map(|stderr| {
+            let (sender, receiver) = channel(OUTPUT_BUFFER_LINES);
+            output_tasks.push(tokio::spawn(forward_lines(stderr, sender, None)));
+            receiver
+        });
+
+        Self {
+            pid: child.id(),
+            child: Some(child),
+            stdout,
+            stderr,
+            log_listener: Some(log_listener),
+            crash,
+            output_tasks,
+            exit_status: None,
+            killed: false,
+            started_at: Instant::now(),
+            launched_at: SystemTime::now(),
+            game_dir,
+            natives_dir: None,
+            on_exit: None,
+        }
+    }
+
+    pub(crate) fn set_natives_dir(&mut self, natives_dir: NativesDir) {
+        self.natives_dir = Some(natives_dir);
+    }
+
+    // run on a blocking thread when the game exits, even if the handle was dropped before
+    pub(crate) fn set_on_exit(&mut self, on_exit: OnExit) {
+        self.on_exit = Some(on_exit);
+    }
+
+    pub fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+
+    // lines of the output of the game, as soon as they are printed
+    // the channel is closed when the game exits, it can only be taken once
+    // at most OUTPUT_BUFFER_LINES lines wait to be received, the next ones are dropped until it's read
+    pub fn take_stdout(&mut self) -> Option<Receiver<String>> {
+        self.stdout.take()
+    }
+
+    pub fn take_stderr(&mut self) -> Option<Receiver<String>> {
+        self.stderr.take()
+    }
+
+    // events of the logs of the game, parsed from the log4j xml of stdout (or its plain lines)
+    // the output printed before it's taken isn't parsed, it can only be taken once
+    pub fn take_log_events(&mut self) -> Option<Receiver<LogEvent>> {
+        let log_listener = self.log_listener.take()?;
+        let (sender, receiver) = channel(OUTPUT_BUFFER_LINES);
+        // when the output is already read, the sender is dropped here and the channel is closed
+        *log_listener.lock().ok()? = Some(sender);
+        Some(receiver)
+    }
+
+    // wait for the game to exit, and for the end of its output to be read
+    // when the game crashed, its crash report is searched and parsed
+    pub async fn wait(&mut self) -> Result<GameExit> {
+        let status = match self.exit_status {
+            Some(exit_status) => exit_status,
+            None => self
+                .child()?
+                .wait()
+                .await
+                .map_err(|e| LauncherError::io(format!("game process {:?}", self.pid), e))?,
+        };
+        for output_task in std::mem::take(&mut self.output_tasks) {
+            let _ = output_task.await;
+        }
+        self.exit_status = Some(status);
+        let (exit_task, play_time) = (self.exit_task(), self.elapsed());
+        let _ = tokio::task::spawn_blocking(move || exit_task(play_time)).await;
+
+        let crashed = self.has_crashed();
+        Ok(GameExit {
+            status,
+            crashed,
+            crash_report: if crashed { self.crash_report() } else { None },
+        })
+    }
+
+    // stop the game, and wait for it to exit
+    pub async fn kill(&mut self) -> Result<()> {
+        if self.exit_status.is_some() {
+            return Ok(());
+        }
+        self.child()?
+            .kill()
+            .await
+            .map_err(|e| LauncherError::io(format!("game process {:?}", self.pid), e))?;
+        // when the kill failed, the game can still crash on its own
+        self.killed = true;
+        self.wait().await.map(|_| ())
+    }
+
+    // exit status of the game, None while it's still running
+    pub fn exit_status(&mut self) -> Option<ExitStatus> {
+        if self.exit_status.is_none() {
+            self.exit_status = self
+                .child
+                .as_mut()
+                .and_then(|child| child.try_wait().ok().flatten());
+            if self.exit_status.is_some() {
+                let (exit_task, play_time) = (self.exit_task(), self.elapsed());
+                run_in_background(move || exit_task(play_time));
+            }
+        }
+        self.exit_status
+    }
+
+    pub fn is_running(&mut self) -> bool {
+        self.exit_status().is_none()
+    }
+
+    // the game printed its crash message, or exited with an error without being killed
+    pub fn has_crashed(&mut self) -> bool {
+        if self.crash.lock().map(|crash| crash.detected).unwrap_or(false) {
+            return true;
+        }
+        !self.killed && self.exit_status().is_some_and(|exit_status| !exit_status.success())
+    }
+
+    // path of the crash report, when the game printed it
+    pub fn crash_report_path(&self) -> Option<String> {
+        self.crash.lock().ok().and_then(|crash| crash.report_path.clone())
+    }
+
+    // the crash report written by the game (crash-reports/) or the jvm (hs_err_pid<pid>.log) since the launch
+    pub fn crash_report(&self) -> Option<CrashReport> {
+        find_crash_report(
+            &self.game_dir,
+            self.crash_report_path().as_deref(),
+            self.pid,
+            self.launched_at,
+        )
+    }
+
+    // time since the launch of the game
+    pub fn elapsed(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+
+    fn child(&mut self) -> Result<&mut Child> {
+        let pid = self.pid;
+        self.child
+            .as_mut()
+            .ok_or_else(|| LauncherError::io(format!("game process {:?}", pid), std::io::ErrorKind::NotFound.into()))
+    }
+
+    // what is done once the game exited : remove its natives, then call on_exit with the time it ran
+    fn exit_task(&mut self) -> impl FnOnce(Duration) + Send + 'static {
+        let (natives_dir, on_exit) = (self.natives_dir.take(), self.on_exit.take());
+        move |play_time| {
+            if let Some(natives_dir) = natives_dir {
+                natives_dir.remove();
+            }
+            if let Some(on_exit) = on_exit {
+                on_exit(play_time);
+            }
+        }
+    }
+}
+
+impl Drop for GameProcess {
+    fn drop(&mut self) {
+        // once exited, the exit task is already run by exit_status
+        if !self.is_running() {
+            return;
+        }
+        let (exit_task, started_at) = (self.exit_task(), self.started_at);
+        match (self.child.take(), tokio::runtime::Handle::try_current()) {
+            (Some(mut child), Ok(handle)) => {
+                handle.spawn(async move {
+                    let _ = child.wait().await;
+                    let _ = tokio::task::spawn_blocking(move || exit_task(started_at.elapsed())).await;
+                });
+            }
+            // nothing can wait for the game anymore, its natives stay locked until the launcher exits
+            _ => std::mem::forget(exit_task),
+        }
+    }
+}
+
+// run a blocking task on the blocking threads of tokio, or right away without a runtime
+fn run_in_background<F: FnOnce() + Send + 'static>(task: F) {
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) => {
+            handle.spawn_blocking(task);
+        }
+        Err(_e) => task(),
+    }
+}
+
+// what is read from stdout besides the lines : the crash message and the log events
+struct StdoutWatcher {
+    crash: Arc<Mutex<CrashState>>,
+    log_parser: LogParser,
+    log_listener: LogListener,
+}
+
+// send each line of the output to the channel, until the game closes it
+async fn forward_lines<R: AsyncRead + Unpin>(
+    output: R,
+    sender: Sender<String>,
+    mut stdout_watcher: Option<StdoutWatcher>,
+) {
+    let mut output = BufReader::new(output);
+    let mut buffer = vec![];
+    loop {
+        buffer.clear();
+        match read_line_capped(&mut output, &mut buffer, MAX_LINE_LENGTH).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        // the jvm writes in the charset of the os (cp1252 on windows...), the invalid bytes are replaced
+        // instead of stopping the reading
+        let line = String::from_utf8_lossy(&buffer);
+        let line = line.trim_end_matches(['\n', '\r']).to_string();
+        if let Some(watcher) = &mut stdout_watcher {
+            detect_crash(&line, &watcher.crash);
+            if let Ok(log_listener) = watcher.log_listener.lock() {
+                if let Some(log_sender) = log_listener.as_ref() {
+                    if let Some(log_event) = watcher.log_parser.push_line(&line) {
+                        let _ = log_sender.try_send(log_event);
+                    }
+                }
+            }
+        }
+        // nobody listens (or the buffer is full), the output still has to be read so the game isn't blocked
+        let _ = sender.try_send(line);
+    }
+}
+
+// read_until a newline, keeping at most max_length bytes of the line in the buffer
+// returns the number of bytes read, 0 at the end of the output
+async fn read_line_capped<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    buffer: &mut Vec<u8>,
+    max_length: usize,
+) -> std::io::Result<usize> {
+    let mut read = 0;
+    loop {
+        let available = reader.fill_buf().await?;
+        if available.is_empty() {
+            return Ok(read);
+        }
+        let (used, end_of_line) = match available.iter().position(|byte| *byte == b'\n') {
+            Some(index) => (index + 1, true),
+            None => (available.len(), false),
+        };
+        let kept = used.min(max_length.saturating_sub(buffer.len()));
+        buffer.extend_from_slice(&available[..kept]);
+        reader.consume(used);
+        read += used;
+        if end_of_line {
+            return Ok(read);
+        }
+    }
+}
+
+fn detect_crash(line: &str, crash: &Mutex<CrashState>) {
+    if !line.contains(CRASH_MARKER) {
+        return;
+    }
+    if let Ok(mut crash) = crash.lock() {
+        crash.detected = true;
+        // the message can be in the cdata of a log event
+        if let Some((_, path)) = line.split_once(CRASH_REPORT_MARKER) {
+            let path = path.split("]]>").next().unwrap_or(path);
+            crash.report_path = Some(path.trim().to_string());
+        }
+    }
+}
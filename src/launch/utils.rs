@@ -1,4 +1,14 @@
-use std::path::Path;
+use std::collections::HashSet;
+
+use crate::update::structs::mc_libs::Library;
+use crate::update::structs::rules::Environment;
+use crate::update::utils::get_lib_path;
+
+// separator of the entries of the classpath, the one of the os java runs on
+#[cfg(windows)]
+pub const CLASSPATH_SEPARATOR: &str = ";";
+#[cfg(not(windows))]
+pub const CLASSPATH_SEPARATOR: &str = ":";
 
 pub struct LaunchBuilder {
     program:String,
@@ -12,22 +22,28 @@ impl LaunchBuilder {
             libs: "".to_string(),
         }
     }
-    
-    // get the string for the libs to use on the cmdline to launch the game 
-    pub fn set_libs_to_launch<S: ToString>(&mut self, lib_path:S, client_path:S) {
-        let mut lib_str = String::new();
-        lib_str.push_str(&format!("{};", client_path.to_string()));
-
-        for entry in std::fs::read_dir(Path::new(&lib_path.to_string())).unwrap() {
-            let entry = entry.unwrap();
-            let path = entry.path();
-            if path.is_file() {
-                lib_str.push_str(path.to_str().unwrap());
-                lib_str.push(';');
-            }
-        }
 
-        self.libs = lib_str;
+    // get the string for the libs to use on the cmdline to launch the game
+    // only the libs of the version allowed in the environment are used, in the order of the manifest,
+    // a lib listed twice (in different versions) is only kept the first time, then comes the client jar
+    pub fn set_libs_to_launch<S: ToString>(
+        &mut self,
+        local_dir_path: &str,
+        libraries: &[Library],
+        environment: &Environment,
+        client_path: S,
+    ) {
+        let mut added_libs = HashSet::new();
+        let mut classpath: Vec<String> = libraries
+            .iter()
+            .filter(|library| library.is_allowed(environment))
+            .filter_map(|library| Some((library.maven_key(), library.main_artifact()?)))
+            .filter(|(maven_key, _)| added_libs.insert(maven_key.clone()))
+            .map(|(_, artifact)| get_lib_path(local_dir_path, &artifact.path))
+            .collect();
+        classpath.push(client_path.to_string());
+
+        self.libs = classpath.join(CLASSPATH_SEPARATOR);
     }
 
     pub fn program(&self) -> &str {
@@ -37,4 +53,4 @@ impl LaunchBuilder {
     pub fn libs(&self) -> &str {
         &self.libs
     }
-}
\ No newline at end of file
+}
@@ -1,24 +1,36 @@
 #![allow(dead_code)]
 #![allow(non_snake_case)]
-mod auth;
-mod launch;
+pub mod auth;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod error;
+pub mod instance;
+pub mod launch;
 pub mod update;
 
 #[cfg(test)]
 mod tests {
-    use serde_json::{json, Value};
+    use std::sync::{Arc, Mutex};
+
+    use serde_json::Value;
+    use crate::auth::account::OfflineAccount;
+    use crate::auth::session::Session;
     use crate::auth::Authenticator;
     use crate::launch;
     use crate::update::java::get_java_zulu_dl_link;
-    use crate::update::structs::mc_libs::Library;
+    use crate::update::downloads::{DownloadElement, DownloadManager, VerifyMode};
+    use crate::update::utils::HashAlgorithm;
+    use crate::update::retry::RetryPolicy;
+    use crate::update::structs::mc_libs::{Argument, Library, LibsRoot, RuleAction};
+    use crate::update::structs::rules::{allowed_arguments, Environment, FEATURE_CUSTOM_RESOLUTION};
     use crate::update::updater::Updater;
 
-    #[test]
-    fn test_auth() {
+    #[tokio::test]
+    async fn test_auth() {
         let auth = Authenticator::new();
-        match auth.authenticate_ms() {
+        match auth.authenticate_ms().await {
             Ok(auth) => {
-                if let Err(e) = auth.get_profile() {
+                if let Err(e) = auth.get_profile().await {
                     eprintln!("Error getting profile: {}", e);
                 }
             }
@@ -26,6 +38,206 @@ mod tests {
         }
     }
 
+    #[test]
+    fn account_manager() {
+        use crate::auth::account::{Account, MicrosoftAccount, Token};
+        use crate::auth::manager::AccountManager;
+        use crate::auth::microsoft::parse_timestamp;
+        use crate::auth::session::xuid_from_token;
+
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(parse_timestamp("2024-06-11T04:00:00.1234567Z"), Some(1718078400));
+        assert!(Token::default().is_expired());
+        assert!(!Token { value: "token".to_string(), expires_at: u64::MAX }.is_expired());
+        // payload {"xuid":"2535405290"}
+        assert_eq!(xuid_from_token("e30.eyJ4dWlkIjoiMjUzNTQwNTI5MCJ9.sig").as_deref(), Some("2535405290"));
+
+        let path = std::env::temp_dir().join("rust_launcher_lib_accounts").join("accounts.json");
+        let _ = std::fs::remove_file(&path);
+        let manager = AccountManager::new(path.display());
+        let mut microsoft = MicrosoftAccount {
+            uuid: "069a79f444e94726a5befca90e38aaf5".to_string(),
+            username: "Notch".to_string(),
+            refresh_token: "refresh".to_string(),
+            ..MicrosoftAccount::default()
+        };
+        manager.add(&Account::Microsoft(microsoft.clone())).unwrap();
+        microsoft.username = "Notch2".to_string();
+        manager.add(&Account::Microsoft(microsoft.clone())).unwrap();
+        let offline = manager.add_offline("Steve").unwrap();
+        assert_eq!(manager.list().unwrap(), vec![Account::Microsoft(microsoft.clone()), offline.clone()]);
+        assert_eq!(manager.default_account().unwrap(), Some(Account::Microsoft(microsoft.clone())));
+        // the tokens are only readable by the user
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        assert!(!path.with_extension("json.tmp").exists());
+
+        manager.set_default(offline.uuid()).unwrap();
+        assert!(manager.set_default("unknown").is_err());
+        let session = tokio::runtime::Runtime::new().unwrap().block_on(manager.default_session()).unwrap();
+        assert_eq!(session.username, "Steve");
+        assert_eq!(session.user_type, "legacy");
+        assert_eq!(Session::from_microsoft(&microsoft).user_type, "msa");
+
+        manager.remove(offline.uuid()).unwrap();
+        assert_eq!(manager.default_account().unwrap(), Some(Account::Microsoft(microsoft.clone())));
+
+        // the tokens are still valid : nothing is refreshed, and the file isn't written again
+        let logged_in = MicrosoftAccount {
+            minecraft_token: Token { value: "token".to_string(), expires_at: u64::MAX },
+            ..microsoft
+        };
+        manager.add(&Account::Microsoft(logged_in.clone())).unwrap();
+        let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        let session = tokio::runtime::Runtime::new().unwrap().block_on(manager.session(&logged_in.uuid)).unwrap();
+        assert_eq!(session.access_token, "token");
+        assert_eq!(std::fs::metadata(&path).unwrap().modified().unwrap(), modified);
+
+        // the accounts added at the same time are all kept
+        std::thread::scope(|scope| {
+            for index in 0..8 {
+                let manager = &manager;
+                scope.spawn(move || manager.add_offline(&format!("Player{}", index)).unwrap());
+            }
+        });
+        assert_eq!(manager.list().unwrap().len(), 9);
+    }
+
+    #[test]
+    fn offline_accounts() {
+        let account = OfflineAccount::new("Notch").unwrap();
+        // uuid given by the game to Notch on an offline server
+        assert_eq!(account.uuid, "b50ad385829d3141a2167e7d7539ba7f");
+        for username in ["ab", "a_name_too_long_17", "with space", "é_accent", ""] {
+            assert!(OfflineAccount::new(username).is_err(), "{} should be refused", username);
+        }
+
+        let session = Session::from_offline(&account);
+        assert_eq!(session.user_type, "legacy");
+        assert_eq!(session.uuid, account.uuid);
+    }
+
+    #[tokio::test]
+    async fn device_code_login_cancelled() {
+        use crate::auth::device_code::DeviceCodeLogin;
+        use crate::error::LauncherError;
+
+        let login = DeviceCodeLogin::new(|_prompt| panic!("the login was cancelled before the code"));
+        login.cancel_token().cancel();
+        let result = Authenticator::new().authenticate_ms_with(login).await;
+        assert!(matches!(result, Err(LauncherError::LoginCancelled)));
+    }
+
+    // answer the requests of the login like the Microsoft, Xbox Live and Minecraft servers
+    async fn mock_auth_server(listener: tokio::net::TcpListener, challenge: Arc<Mutex<String>>) {
+        use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        use base64::Engine;
+        use sha2::{Digest, Sha256};
+        use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+
+        while let Ok((stream, _address)) = listener.accept().await {
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).await.unwrap();
+            let mut content_length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).await.unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).await.unwrap();
+            let form = reqwest::Url::parse(&format!("http://localhost/?{}", String::from_utf8_lossy(&body))).unwrap();
+            let param = |name: &str| form.query_pairs().find(|(key, _)| key == name).map(|(_, value)| value.to_string());
+
+            let xbox = r#"{"NotAfter":"2100-01-01T00:00:00Z","Token":"xbox","DisplayClaims":{"xui":[{"uhs":"hash"}]}}"#;
+            let (status, json) = match request_line.split_whitespace().nth(1).unwrap_or_default() {
+                "/consumers/oauth2/v2.0/token" => {
+                    // the verifier must be the secret of the challenge given to the login page
+                    let verifier = param("code_verifier").unwrap_or_default();
+                    let verified = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes())) == *challenge.lock().unwrap();
+                    if param("code").as_deref() == Some("mock_code") && verified {
+                        ("200 OK", r#"{"access_token":"microsoft","refresh_token":"refresh","expires_in":3600}"#.to_string())
+                    } else {
+                        ("400 Bad Request", r#"{"error":"invalid_grant"}"#.to_string())
+                    }
+                }
+                "/user/authenticate" | "/xsts/authorize" => ("200 OK", xbox.to_string()),
+                // the token is a jwt with the payload {"xuid":"2535405290"}
+                "/authentication/login_with_xbox" => (
+                    "200 OK",
+                    r#"{"access_token":"e30.eyJ4dWlkIjoiMjUzNTQwNTI5MCJ9.sig","expires_in":86400}"#.to_string(),
+                ),
+                "/minecraft/profile" => (
+                    "200 OK",
+                    r#"{"id":"069a79f444e94726a5befca90e38aaf5","name":"Notch","skins":[],"capes":[],"profileActions":{}}"#
+                        .to_string(),
+                ),
+                _ => ("404 Not Found", "{}".to_string()),
+            };
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                json.len(),
+                json
+            );
+            let mut stream = reader.into_inner();
+            stream.write_all(response.as_bytes()).await.unwrap();
+            let _ = stream.shutdown().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn auth_code_login() {
+        use crate::auth::auth_code::AuthCodeLogin;
+        use crate::auth::endpoints::Endpoints;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let challenge = Arc::new(Mutex::new(String::new()));
+        tokio::spawn(mock_auth_server(listener, challenge.clone()));
+
+        // the browser of the user : logs in, and is redirected to the listener of the launcher
+        let login = AuthCodeLogin::new(move |url| {
+            let url = reqwest::Url::parse(url).unwrap();
+            let param = |name: &str| url.query_pairs().find(|(key, _)| key == name).unwrap().1.to_string();
+            *challenge.lock().unwrap() = param("code_challenge");
+            let redirect_uri = reqwest::Url::parse(&param("redirect_uri")).unwrap();
+            assert_eq!(redirect_uri.host_str(), Some("127.0.0.1"));
+            let redirect = format!("{}?code=mock_code&state={}", redirect_uri, param("state"));
+            tokio::spawn(async move {
+                // a connection opened in advance by the browser, and never used
+                let port = redirect_uri.port().unwrap();
+                let _preconnect = tokio::net::TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+                // requests of another page, without the state of the login : ignored
+                for forged in ["?code=forged_code&state=wrong", "?error=access_denied", "favicon.ico"] {
+                    let response = reqwest::get(format!("{}{}", redirect_uri, forged)).await.unwrap();
+                    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+                }
+                reqwest::get(redirect).await.unwrap().text().await.unwrap()
+            });
+        });
+
+        let mut authenticator = Authenticator::new();
+        authenticator.set_endpoints(Endpoints::with_base_url(&base_url));
+        let authenticator = authenticator.authenticate_ms_browser(login).await.unwrap();
+        let account = authenticator.account().unwrap();
+        assert_eq!(account.username, "Notch");
+        assert_eq!(account.refresh_token, "refresh");
+        assert_eq!(Session::from_microsoft(account).xuid, "2535405290");
+    }
+
     #[test]
     fn parsing() {
         let libraries_json = r#"
@@ -49,29 +261,1021 @@ mod tests {
 
         let libraries: Vec<Library> =
             serde_json::from_value(json_object["libraries"].clone()).unwrap();
+        assert_eq!(libraries[0].name, "com.mojang:netty:1.8.8");
     }
 
     #[test]
-    fn check_files() {
-        let runtime = tokio::runtime::Runtime::new().unwrap();
+    fn parsing_version_json() {
+        let version_json = r#"
+{
+    "arguments": {
+        "game": ["--username", "${auth_player_name}", {"rules": [{"action": "allow", "features": {"is_demo_user": true}}], "value": "--demo"}],
+        "jvm": [{"rules": [{"action": "allow", "os": {"name": "osx"}}], "value": ["-XstartOnFirstThread"]}, "-cp", "${classpath}"]
+    },
+    "assetIndex": {"id": "17", "sha1": "fab15439bdef669e389e25e815eee8f1b2aa915e", "size": 447033, "totalSize": 799252591, "url": "https://piston-meta.mojang.com/v1/packages/fab15439bdef669e389e25e815eee8f1b2aa915e/17.json"},
+    "assets": "17",
+    "downloads": {
+        "client": {"sha1": "0e9a07b9bb3390602f977073aa12884a4ce12431", "size": 26836906, "url": "https://piston-data.mojang.com/v1/objects/0e9a07b9bb3390602f977073aa12884a4ce12431/client.jar"},
+        "server": {"sha1": "450698d1863ab5180c25d7c804ef0fe6369dd1ba", "size": 51420480, "url": "https://piston-data.mojang.com/v1/objects/450698d1863ab5180c25d7c804ef0fe6369dd1ba/server.jar"}
+    },
+    "id": "1.21",
+    "javaVersion": {"component": "java-runtime-delta", "majorVersion": 21},
+    "libraries": [
+        {
+            "name": "org.lwjgl.lwjgl:lwjgl-platform:2.9.4-nightly-20150209",
+            "downloads": {"classifiers": {"natives-linux": {"path": "org/lwjgl/lwjgl/lwjgl-platform/2.9.4-nightly-20150209/lwjgl-platform-2.9.4-nightly-20150209-natives-linux.jar", "sha1": "931074f46c795d2f7b30ed6395df5715cfd7675b", "size": 578680, "url": "https://libraries.minecraft.net/org/lwjgl/lwjgl/lwjgl-platform/2.9.4-nightly-20150209/lwjgl-platform-2.9.4-nightly-20150209-natives-linux.jar"}}},
+            "extract": {"exclude": ["META-INF/"]},
+            "natives": {"linux": "natives-linux", "windows": "natives-windows-${arch}"},
+            "rules": [{"action": "allow"}, {"action": "disallow", "os": {"name": "osx"}}]
+        }
+    ],
+    "logging": {"client": {"argument": "-Dlog4j.configurationFile=${path}", "file": {"id": "client-1.12.xml", "sha1": "bd65e7d2e3c237be76cfbef4c2405033d7f91521", "size": 888, "url": "https://piston-data.mojang.com/v1/objects/bd65e7d2e3c237be76cfbef4c2405033d7f91521/client-1.12.xml"}, "type": "log4j2-xml"}},
+    "mainClass": "net.minecraft.client.main.Main",
+    "releaseTime": "2024-06-13T08:24:03+00:00",
+    "time": "2024-06-13T08:24:03+00:00",
+    "type": "release"
+}
+"#;
+        let root = LibsRoot::parse_json(version_json.to_string()).unwrap();
+        assert_eq!(root.main_class, "net.minecraft.client.main.Main");
+        assert_eq!(root.java_version.major_version, 21);
+        assert!(root.downloads.server.is_some());
+        assert!(root.minecraft_arguments.is_none());
 
-        runtime.block_on(async {
-            println!(
-                "{}",
-                get_java_zulu_dl_link("11.0.11".to_string()).await.unwrap()
-            );
-        });
+        let library = &root.libraries[0];
+        assert!(library.downloads.artifact.is_none());
+        assert!(library.downloads.classifiers.as_ref().unwrap().contains_key("natives-linux"));
+        assert_eq!(library.extract.as_ref().unwrap().exclude, vec!["META-INF/"]);
+        assert_eq!(library.rules.as_ref().unwrap()[1].action, RuleAction::Disallow);
+
+        let arguments = root.arguments.unwrap();
+        assert_eq!(arguments.game[1], Argument::Plain("${auth_player_name}".to_string()));
+        match &arguments.jvm[0] {
+            Argument::Conditional { value, .. } => assert_eq!(value.values(), vec!["-XstartOnFirstThread"]),
+            Argument::Plain(_) => panic!("expected a conditional argument"),
+        }
+        assert_eq!(root.logging.unwrap().client.unwrap().file.id, "client-1.12.xml");
+
+        assert!(LibsRoot::parse_json("{\"id\": 1}".to_string()).is_err());
     }
 
     #[test]
-    fn launch_test() {
+    fn rules_evaluation() {
+        let arguments: Vec<Argument> = serde_json::from_value(serde_json::json!([
+            "--username",
+            {"rules": [{"action": "allow", "features": {"has_custom_resolution": true}}], "value": ["--width", "${resolution_width}"]},
+            {"rules": [{"action": "allow", "os": {"name": "windows", "version": "^10\\."}}], "value": "-Dos.name=Windows 10"},
+            {"rules": [{"action": "allow", "os": {"arch": "x86"}}], "value": "-Xss1M"}
+        ]))
+        .unwrap();
+        let library: Library = serde_json::from_value(serde_json::json!({
+            "name": "ca.weblite:java-objc-bridge:1.0.0",
+            "rules": [{"action": "allow"}, {"action": "disallow", "os": {"name": "osx"}}]
+        }))
+        .unwrap();
+
+        let mut environment = Environment {
+            os_name: "windows".to_string(),
+            os_arch: "x86".to_string(),
+            os_version: "10.0.19045".to_string(),
+            features: Default::default(),
+        };
+        assert_eq!(
+            allowed_arguments(&arguments, &environment),
+            vec!["--username", "-Dos.name=Windows 10", "-Xss1M"]
+        );
+        assert!(library.is_allowed(&environment));
+
+        environment.os_name = "osx".to_string();
+        environment.os_arch = "arm64".to_string();
+        environment.set_feature(FEATURE_CUSTOM_RESOLUTION, true);
+        assert_eq!(
+            allowed_arguments(&arguments, &environment),
+            vec!["--username", "--width", "${resolution_width}"]
+        );
+        assert!(!library.is_allowed(&environment));
+    }
+
+    #[test]
+    fn launch_arguments() {
+        use crate::launch::arguments::{game_arguments, jvm_arguments, ArgumentsVariables};
+
+        let mut libs_manifest: LibsRoot = serde_json::from_value(serde_json::json!({
+            "id": "1.7.10",
+            "mainClass": "net.minecraft.client.main.Main",
+            "minecraftArguments": "--username ${auth_player_name} --session ${auth_session} --unknown ${foo}",
+            "assetIndex": {"id": "1.7.10", "sha1": "", "size": 0, "totalSize": 0, "url": ""},
+            "downloads": {"client": {"sha1": "", "size": 0, "url": ""}}
+        }))
+        .unwrap();
+        let environment = Environment::current();
+        let variables = ArgumentsVariables::from([
+            ("auth_player_name", "knightmar67".to_string()),
+            ("auth_session", "token:abc:def".to_string()),
+            ("natives_directory", "/natives".to_string()),
+            ("classpath", "a.jar".to_string()),
+        ]);
+
+        assert_eq!(
+            jvm_arguments(&libs_manifest, &environment, &variables),
+            vec!["-Djava.library.path=/natives", "-cp", "a.jar"]
+        );
+        assert_eq!(
+            game_arguments(&libs_manifest, &environment, &variables),
+            vec!["--username", "knightmar67", "--session", "token:abc:def", "--unknown", "${foo}"]
+        );
+
+        libs_manifest.arguments = serde_json::from_value(serde_json::json!({
+            "game": ["--username", "${auth_player_name}"],
+            "jvm": ["-cp", "${classpath}"]
+        }))
+        .unwrap();
+        assert_eq!(
+            game_arguments(&libs_manifest, &environment, &variables),
+            vec!["--username", "knightmar67"]
+        );
+        assert_eq!(jvm_arguments(&libs_manifest, &environment, &variables), vec!["-cp", "a.jar"]);
+    }
+
+    #[test]
+    fn classpath_from_manifest() {
+        use crate::launch::utils::{LaunchBuilder, CLASSPATH_SEPARATOR};
+
+        let libraries: Vec<Library> = serde_json::from_value(serde_json::json!([
+            {"name": "com.mojang:brigadier:1.2.9", "downloads": {"artifact": {"path": "brigadier-1.2.9.jar", "sha1": "", "size": 0, "url": ""}}},
+            {"name": "org.lwjgl:lwjgl:3.3.3", "downloads": {"artifact": {"path": "lwjgl-3.3.3.jar", "sha1": "", "size": 0, "url": ""}}},
+            {"name": "org.lwjgl:lwjgl:3.3.3:natives-linux", "downloads": {"artifact": {"path": "lwjgl-3.3.3-natives-linux.jar", "sha1": "", "size": 0, "url": ""}}},
+            {"name": "org.lwjgl:lwjgl:3.2.2", "downloads": {"artifact": {"path": "lwjgl-3.2.2.jar", "sha1": "", "size": 0, "url": ""}}},
+            {"name": "ca.weblite:java-objc-bridge:1.1", "downloads": {"artifact": {"path": "java-objc-bridge-1.1.jar", "sha1": "", "size": 0, "url": ""}},
+                "rules": [{"action": "allow", "os": {"name": "osx"}}]}
+        ]))
+        .unwrap();
+        let mut environment = Environment::current();
+        environment.os_name = "linux".to_string();
+
+        let mut builder = LaunchBuilder::new("java");
+        builder.set_libs_to_launch("/mc/", &libraries, &environment, "/mc/client.jar");
+        let classpath: Vec<&str> = builder.libs().split(CLASSPATH_SEPARATOR).collect();
+        let file_names: Vec<&str> = classpath
+            .iter()
+            .map(|entry| std::path::Path::new(entry).file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(
+            file_names,
+            vec!["brigadier-1.2.9.jar", "lwjgl-3.3.3.jar", "lwjgl-3.3.3-natives-linux.jar", "client.jar"]
+        );
+    }
+
+    #[test]
+    fn maven_layout() {
+        use crate::update::structs::mc_libs::maven_path;
+
+        assert_eq!(
+            maven_path("org.lwjgl:lwjgl:3.3.3:natives-linux").unwrap(),
+            "org/lwjgl/lwjgl/3.3.3/lwjgl-3.3.3-natives-linux.jar"
+        );
+        assert_eq!(
+            maven_path("de.oceanlabs.mcp:mcp_config:1.20.1@zip").unwrap(),
+            "de/oceanlabs/mcp/mcp_config/1.20.1/mcp_config-1.20.1.zip"
+        );
+        assert!(maven_path("not-a-maven-name").is_none());
+
+        // the libs of the old flat libs/ dir are moved to the maven layout
+        let dir = std::env::temp_dir().join("rust_launcher_lib_maven_layout");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("libs")).unwrap();
+        std::fs::write(dir.join("libs").join("sponge-mixin-0.13.3.jar"), b"mixin").unwrap();
+        let library: Library = serde_json::from_value(serde_json::json!({
+            "name": "net.fabricmc:sponge-mixin:0.13.3",
+            "url": "https://maven.fabricmc.net/"
+        }))
+        .unwrap();
+        let artifact = library.main_artifact().unwrap();
+        assert_eq!(
+            artifact.url,
+            "https://maven.fabricmc.net/net/fabricmc/sponge-mixin/0.13.3/sponge-mixin-0.13.3.jar"
+        );
+
+        let local_dir_path = dir.to_string_lossy().to_string() + std::path::MAIN_SEPARATOR_STR;
+        crate::update::utils::migrate_flat_libraries(&local_dir_path, &[library]).unwrap();
+        let lib_path = crate::update::utils::get_lib_path(&local_dir_path, &artifact.path);
+        assert_eq!(std::fs::read(lib_path).unwrap(), b"mixin");
+        assert!(!dir.join("libs").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn version_files_layout() {
+        use crate::update::utils::{get_client_jar_path, get_version_json_path};
+
+        let dir = std::env::temp_dir().join("rust_launcher_lib_version_files");
+        let _ = std::fs::remove_dir_all(&dir);
+        let local_dir_path = dir.to_string_lossy().to_string() + std::path::MAIN_SEPARATOR_STR;
+        let version_dir = dir.join("versions").join("1.21");
+        assert_eq!(get_client_jar_path(&local_dir_path, "1.21"), version_dir.join("1.21.jar").to_string_lossy());
+        assert_eq!(get_version_json_path(&local_dir_path, "1.21"), version_dir.join("1.21.json").to_string_lossy());
+
+        // the json is saved as downloaded, with the fields LibsRoot doesn't know
+        let version_json = r#"{
+    "assetIndex": {"id": "17", "sha1": "fab15439bdef669e389e25e815eee8f1b2aa915e", "size": 447033, "totalSize": 799252591, "url": "https://piston-meta.mojang.com/v1/packages/fab15439bdef669e389e25e815eee8f1b2aa915e/17.json"},
+    "complianceLevel": 1,
+    "downloads": {"client": {"sha1": "0e9a07b9bb3390602f977073aa12884a4ce12431", "size": 26836906, "url": "https://piston-data.mojang.com/v1/objects/0e9a07b9bb3390602f977073aa12884a4ce12431/client.jar"}},
+    "id": "1.21",
+    "mainClass": "net.minecraft.client.main.Main"
+}"#;
+        let mut updater = Updater::new("1.21");
+        updater.set_local_dir_path(local_dir_path.clone());
+        updater.set_version_json(version_json.to_string()).unwrap();
+        updater.save_version_json().await.unwrap();
+        assert_eq!(std::fs::read_to_string(version_dir.join("1.21.json")).unwrap(), version_json);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn instances_management() {
+        use crate::instance::{Instance, InstanceManager};
+
+        let dir = std::env::temp_dir().join("rust_launcher_lib_instances");
+        let _ = std::fs::remove_dir_all(&dir);
+        let manager = InstanceManager::new(dir.to_string_lossy().to_string() + std::path::MAIN_SEPARATOR_STR);
+
+        let mut instance = Instance::new("survival", "1.21");
+        instance.max_memory = Some(4096);
+        manager.create(&instance).unwrap();
+        assert!(manager.create(&instance).is_err());
+        assert!(manager.create(&Instance::new("../escape", "1.21")).is_err());
+        std::fs::write(manager.game_dir("survival") + "options.txt", "fov:90").unwrap();
+
+        let copy = manager.duplicate("survival", "creative").unwrap();
+        assert_eq!(copy.all_jvm_args(), vec!["-Xmx4096M"]);
+        assert_eq!(std::fs::read_to_string(manager.game_dir("creative") + "options.txt").unwrap(), "fov:90");
+
+        // the launcher uses the root of the manager as it is, not a dir of the home
+        let launcher = manager.launcher(&copy).unwrap();
+        assert_eq!(launcher.local_dir_path(), manager.local_dir_path());
+        assert_eq!(launcher.game_dir(), manager.game_dir("creative"));
+        // the mod loaders are refused instead of launching the vanilla game
+        let mut modded = Instance::new("modded", "1.21");
+        modded.loader = crate::instance::Loader::Fabric;
+        assert!(matches!(manager.launcher(&modded), Err(crate::error::LauncherError::InvalidInstance(_))));
+        assert!(manager.updater(&modded).is_err());
+
+        manager.rename("creative", "hardcore").unwrap();
+        let names: Vec<String> = manager.list().unwrap().into_iter().map(|instance| instance.name).collect();
+        assert_eq!(names, vec!["hardcore", "survival"]);
+
+        manager.delete("hardcore").unwrap();
+        assert!(matches!(manager.get("hardcore"), Err(crate::error::LauncherError::InstanceNotFound(_))));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn game_process_output() {
+        use crate::launch::process::GameProcess;
+
+        let child = tokio::process::Command::new("sh")
+            .args(["-c", "echo starting; echo '#@!@# Game crashed! Crash report saved to: #@!@# /tmp/crash.txt'; echo oops >&2; exit 255"])
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut process = GameProcess::new(child, std::env::temp_dir().to_string_lossy().to_string());
+        assert!(process.pid().is_some());
+        let mut stdout = process.take_stdout().unwrap();
+        let mut stderr = process.take_stderr().unwrap();
+        let mut log_events = process.take_log_events().unwrap();
+        assert!(process.take_log_events().is_none());
+
+        let exit = process.wait().await.unwrap();
+        assert_eq!(log_events.recv().await.unwrap().message, "starting");
+        assert!(!exit.status.success());
+        assert!(exit.crashed);
+        assert_eq!(stdout.recv().await.unwrap(), "starting");
+        assert!(stdout.recv().await.unwrap().contains("Game crashed!"));
+        assert_eq!(stdout.recv().await, None);
+        assert_eq!(stderr.recv().await.unwrap(), "oops");
+        assert!(process.has_crashed());
+        assert_eq!(process.crash_report_path().unwrap(), "/tmp/crash.txt");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn game_process_on_exit() {
+        use crate::launch::process::GameProcess;
+        use std::time::Duration;
+
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let spawn = || {
+            let child = tokio::process::Command::new("sh").args(["-c", "sleep 0.2"]).spawn().unwrap();
+            let mut process = GameProcess::new(child, std::env::temp_dir().to_string_lossy().to_string());
+            let sender = sender.clone();
+            process.set_on_exit(Box::new(move |play_time| {
+                let _ = sender.send(play_time);
+            }));
+            process
+        };
+
+        spawn().wait().await.unwrap();
+        assert!(receiver.try_recv().unwrap() >= Duration::from_millis(200));
+        // the game goes on without its handle, its exit is still seen
+        drop(spawn());
+        let play_time = tokio::time::timeout(Duration::from_secs(5), receiver.recv()).await.unwrap();
+        assert!(play_time.unwrap() >= Duration::from_millis(200));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn game_process_invalid_utf8() {
+        use crate::launch::process::GameProcess;
+
+        // "café" in cp1252, then the crash message that must still be seen
+        let child = tokio::process::Command::new("sh")
+            .args(["-c", "printf 'caf\\351\\r\\n'; echo '#@!@# Game crashed!'; echo after"])
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut process = GameProcess::new(child, std::env::temp_dir().to_string_lossy().to_string());
+        let mut stdout = process.take_stdout().unwrap();
+
+        let exit = process.wait().await.unwrap();
+        assert!(exit.crashed);
+        assert_eq!(stdout.recv().await.unwrap(), "caf\u{FFFD}");
+        assert!(stdout.recv().await.unwrap().contains("Game crashed!"));
+        assert_eq!(stdout.recv().await.unwrap(), "after");
+        assert_eq!(stdout.recv().await, None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn game_process_long_line() {
+        use crate::launch::process::{GameProcess, MAX_LINE_LENGTH};
+
+        // a line of 1 MB without a newline before the end of the output
+        let child = tokio::process::Command::new("sh")
+            .args(["-c", "head -c 1048576 /dev/zero | tr '\\0' a; echo; echo after; printf last"])
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut process = GameProcess::new(child, std::env::temp_dir().to_string_lossy().to_string());
+        let mut stdout = process.take_stdout().unwrap();
+        process.wait().await.unwrap();
+
+        assert_eq!(stdout.recv().await.unwrap(), "a".repeat(MAX_LINE_LENGTH));
+        assert_eq!(stdout.recv().await.unwrap(), "after");
+        assert_eq!(stdout.recv().await.unwrap(), "last");
+        assert_eq!(stdout.recv().await, None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn game_process_output_is_bounded() {
+        use crate::launch::process::GameProcess;
+
+        let child = tokio::process::Command::new("sh")
+            .args(["-c", "seq 1 10005"])
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut process = GameProcess::new(child, std::env::temp_dir().to_string_lossy().to_string());
+        let mut stdout = process.take_stdout().unwrap();
+        process.wait().await.unwrap();
+
+        // nothing was read while the game ran, the lines after the buffer are dropped
+        let mut lines = vec![];
+        while let Some(line) = stdout.recv().await {
+            lines.push(line);
+        }
+        assert_eq!(lines.len(), 10_000);
+        assert_eq!(lines.last().unwrap(), "10000");
+    }
+
+    #[test]
+    fn log_events_parsing() {
+        use crate::launch::logs::{LogLevel, LogParser};
+
+        let output = r#"[LWJGL] Version 3.3.3
+<log4j:Event logger="net.minecraft.client.Minecraft" timestamp="1718000000000" level="INFO" thread="Render thread">
+  <log4j:Message><![CDATA[Setting user: knightmar67]]></log4j:Message>
+</log4j:Event>
+
+<log4j:Event logger="net.minecraft.Util" timestamp="1718000000123" level="ERROR" thread="Worker &quot;Main&quot;">
+  <log4j:Message>a &lt;failed&gt; task</log4j:Message>
+  <log4j:Throwable><![CDATA[java.lang.IllegalStateException: oops
+	at net.minecraft.Util.run(Util.java:42)
+]]></log4j:Throwable>
+</log4j:Event>"#;
+
+        let mut parser = LogParser::default();
+        let events: Vec<_> = output.lines().filter_map(|line| parser.push_line(line)).collect();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].message, "[LWJGL] Version 3.3.3");
+        assert_eq!(events[0].timestamp, None);
+
+        assert_eq!(events[1].level, LogLevel::Info);
+        assert_eq!(events[1].thread, "Render thread");
+        assert_eq!(events[1].logger, "net.minecraft.client.Minecraft");
+        assert_eq!(events[1].timestamp, Some(1718000000000));
+        assert_eq!(events[1].message, "Setting user: knightmar67");
+
+        assert_eq!(events[2].level, LogLevel::Error);
+        assert_eq!(events[2].thread, "Worker \"Main\"");
+        assert_eq!(events[2].message, "a <failed> task");
+        assert!(events[2].throwable.as_ref().unwrap().starts_with("java.lang.IllegalStateException: oops"));
+
+        // an event that is never closed isn't kept forever
+        let mut parser = LogParser::default();
+        assert_eq!(parser.push_line("<log4j:Event level=\"INFO\">"), None);
+        let line = "x".repeat(1024);
+        let event = (0..2000).find_map(|_| parser.push_line(&line)).unwrap();
+        assert!(event.message.starts_with("<log4j:Event"));
+        assert_eq!(parser.push_line("after").unwrap().message, "after");
+    }
+
+    #[test]
+    fn crash_report_parsing() {
+        use crate::launch::crash::CrashReport;
+
+        let game_report = "---- Minecraft Crash Report ----
+// Who set us up the TNT?
+
+Time: 2024-06-10 12:00:00
+Description: Rendering overlay
+
+java.lang.NullPointerException: Cannot invoke \"Object.toString()\"
+\tat net.minecraft.client.gui.Gui.render(Gui.java:42)
+\tat net.minecraft.client.Minecraft.runTick(Minecraft.java:1200)
+
+
+A detailed walkthrough of the error, its code path and all known details is as follows:
+---------------------------------------------------------------------------------------
+
+-- System Details --
+Details:
+\tMinecraft Version: 1.21
+\tJava Version: 21.0.3, Eclipse Adoptium
+\tFabric Mods:
+\t\tfabric-api: Fabric API 0.100.0+1.21
+\t\tsodium: Sodium 0.5.9
+\tLaunched Version: fabric-loader-0.15.11-1.21
+";
+        let report = CrashReport::parse_game_report("crash.txt".to_string(), game_report);
+        assert_eq!(report.description, "Rendering overlay");
+        assert_eq!(report.exception, "java.lang.NullPointerException: Cannot invoke \"Object.toString()\"");
+        assert_eq!(report.stack_trace.len(), 2);
+        assert_eq!(report.mods, vec!["fabric-api: Fabric API 0.100.0+1.21", "sodium: Sodium 0.5.9"]);
+        assert!(report.system_details.contains(&("Minecraft Version".to_string(), "1.21".to_string())));
+        assert!(report.system_details.iter().any(|(key, _)| key == "Launched Version"));
+
+        let jvm_report = "#
+# A fatal error has been detected by the Java Runtime Environment:
+#
+#  SIGSEGV (0xb) at pc=0x00007f1c2a3b4c5d, pid=4242, tid=4243
+#
+# JRE version: OpenJDK Runtime Environment (21.0.3+9) (build 21.0.3+9-LTS)
+# Java VM: OpenJDK 64-Bit Server VM (21.0.3+9-LTS, mixed mode, linux-amd64)
+# Problematic frame:
+# C  [liblwjgl_opengl.so+0x1c5d]
+#
+
+---------------  T H R E A D  ---------------
+
+Native frames: (J=compiled Java code, j=interpreted, Vv=VM code, C=native code)
+C  [liblwjgl_opengl.so+0x1c5d]
+j  org.lwjgl.opengl.GL11C.glDrawElements(IIIJ)V+0
+
+";
+        let report = CrashReport::parse_jvm_report("hs_err_pid4242.log".to_string(), jvm_report);
+        assert_eq!(report.description, "SIGSEGV (0xb) at pc=0x00007f1c2a3b4c5d, pid=4242, tid=4243");
+        assert_eq!(report.exception, "C  [liblwjgl_opengl.so+0x1c5d]");
+        assert_eq!(report.stack_trace.len(), 2);
+        assert_eq!(report.system_details.len(), 2);
+    }
+
+    #[test]
+    fn natives_extraction() {
+        use std::io::Write;
+
+        let dir = std::env::temp_dir().join("rust_launcher_lib_natives_extraction");
+        let _ = std::fs::remove_dir_all(&dir);
+        let local_dir_path = dir.to_string_lossy().to_string() + std::path::MAIN_SEPARATOR_STR;
+
+        let classifier = format!("natives-{}", crate::update::structs::mojang_os_name());
+        let jar_name = format!("lwjgl-platform-2.9.4-{}.jar", classifier);
+        let jar_path = crate::update::utils::get_lib_path(&local_dir_path, &jar_name);
+        std::fs::create_dir_all(std::path::Path::new(&jar_path).parent().unwrap()).unwrap();
+        let mut jar = zip::ZipWriter::new(std::fs::File::create(&jar_path).unwrap());
+        let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        jar.start_file("META-INF/MANIFEST.MF", options).unwrap();
+        jar.write_all(b"Manifest-Version: 1.0").unwrap();
+        jar.start_file("liblwjgl.so", options).unwrap();
+        jar.write_all(b"native").unwrap();
+        jar.finish().unwrap();
+
+        let library: Library = serde_json::from_value(serde_json::json!({
+            "name": "org.lwjgl.lwjgl:lwjgl-platform:2.9.4",
+            "downloads": {"classifiers": {classifier.clone(): {"path": jar_name, "sha1": "", "size": 0, "url": ""}}},
+            "natives": {crate::update::structs::mojang_os_name(): classifier},
+            "extract": {"exclude": ["META-INF/"]}
+        }))
+        .unwrap();
+
+        let extract = |version: &str| {
+            crate::launch::natives::extract_natives(&local_dir_path, version, std::slice::from_ref(&library), &Environment::current()).unwrap()
+        };
+        let first = extract("1.8.9");
+        let first_path = std::path::Path::new(first.path()).to_path_buf();
+        assert_eq!(std::fs::read(first_path.join("liblwjgl.so")).unwrap(), b"native");
+        assert!(!first_path.join("META-INF").exists());
+
+        // the natives of a running game are locked, the next launches keep them, even in the same millisecond
+        let second = extract("1.8.9");
+        assert_ne!(first.path(), second.path());
+        assert!(first_path.exists());
+        // the old natives of the other versions are removed too
+        drop(first);
+        let third = extract("1.7.10");
+        assert!(!first_path.exists());
+        assert!(std::path::Path::new(second.path()).exists());
+
+        // removed when the game exits
+        let third_path = third.path().to_string();
+        third.remove();
+        assert!(!std::path::Path::new(&third_path).exists());
+        drop(second);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn legacy_assets_layout() {
+        use crate::launch::assets::prepare_assets;
+
+        let dir = std::env::temp_dir().join("rust_launcher_lib_legacy_assets");
+        let _ = std::fs::remove_dir_all(&dir);
+        let local_dir_path = dir.to_string_lossy().to_string() + std::path::MAIN_SEPARATOR_STR;
+        let game_dir = dir.join("game").to_string_lossy().to_string();
+
+        let hash = "c2a1a5ecbb6e3a1bd5e5e0ef6b1f0b6b6e3e6a41";
+        let (_, object_path) = crate::update::utils::get_asset_path_from_hash(local_dir_path.clone(), hash);
+        std::fs::create_dir_all(std::path::Path::new(&object_path).parent().unwrap()).unwrap();
+        std::fs::write(object_path, b"sound").unwrap();
+        let objects = serde_json::json!({"sound/step.ogg": {"hash": hash, "size": 5}, "../escape.ogg": {"hash": hash, "size": 5}});
+        let indexes_dir = dir.join("assets").join("indexes");
+        std::fs::create_dir_all(&indexes_dir).unwrap();
+        std::fs::write(indexes_dir.join("legacy.json"), serde_json::json!({"virtual": true, "objects": objects}).to_string()).unwrap();
+        std::fs::write(indexes_dir.join("pre-1.6.json"), serde_json::json!({"map_to_resources": true, "objects": objects}).to_string()).unwrap();
+        std::fs::write(indexes_dir.join("17.json"), serde_json::json!({"objects": objects}).to_string()).unwrap();
+
+        // 1.6 to 1.7.2
+        let game_assets = prepare_assets(&local_dir_path, &game_dir, "legacy", "https://host/legacy.json").unwrap();
+        let virtual_dir = dir.join("assets").join("virtual").join("legacy");
+        assert_eq!(std::path::Path::new(&game_assets), virtual_dir);
+        assert_eq!(std::fs::read(virtual_dir.join("sound").join("step.ogg")).unwrap(), b"sound");
+        assert!(!dir.join("assets").join("virtual").join("escape.ogg").exists());
+
+        // before 1.6
+        let game_assets = prepare_assets(&local_dir_path, &game_dir, "pre-1.6", "https://host/pre-1.6.json").unwrap();
+        let resources_dir = dir.join("game").join("resources");
+        assert_eq!(std::path::Path::new(&game_assets), resources_dir);
+        assert_eq!(std::fs::read(resources_dir.join("sound").join("step.ogg")).unwrap(), b"sound");
+
+        // the recent versions read the objects by hash
+        let game_assets = prepare_assets(&local_dir_path, &game_dir, "17", "https://host/17.json").unwrap();
+        assert_eq!(game_assets, local_dir_path + "assets" + std::path::MAIN_SEPARATOR_STR);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn retry_delays() {
+        let policy = RetryPolicy::default();
+        for retry in 1..20 {
+            let delay = policy.delay(retry);
+            assert!(delay <= policy.max_delay);
+            assert!(delay >= (policy.base_delay * 2u32.pow(u32::from(retry - 1).min(16))).min(policy.max_delay) / 2);
+        }
+        assert!(policy.can_retry(4));
+        assert!(!policy.can_retry(5));
+        // the jitter spreads the retries of the files that failed together
+        let delays: std::collections::HashSet<_> = (0..20).map(|_| policy.delay(3)).collect();
+        assert!(delays.len() > 1);
+
+        // no real max_delay, the jitter must not overflow
+        let policy = RetryPolicy {
+            base_delay: std::time::Duration::from_secs(u64::MAX / 4),
+            max_delay: std::time::Duration::MAX,
+            ..RetryPolicy::default()
+        };
+        for retry in [1, 2, 16, u8::MAX] {
+            let delay = policy.delay(retry);
+            assert!(delay <= policy.max_delay);
+            assert!(delay >= (policy.base_delay / 2).min(policy.max_delay / 2));
+        }
+    }
+
+    // serve the same content to every request on a local port, handling the Range header
+    // the starts of the ranges asked by the requests are kept in the returned vec
+    async fn spawn_file_server(content: Vec<u8>) -> (String, Arc<Mutex<Vec<Option<usize>>>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let ranges = Arc::new(Mutex::new(vec![]));
+        let requested_ranges = ranges.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = vec![0u8; 4096];
+                let read = stream.read(&mut request).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&request[..read]).to_lowercase();
+                let start = request
+                    .lines()
+                    .find_map(|line| line.strip_prefix("range: bytes="))
+                    .and_then(|range| range.trim().trim_end_matches('-').parse::<usize>().ok());
+                requested_ranges.lock().unwrap().push(start);
+
+                let header = match start {
+                    Some(start) => format!(
+                        "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {}-{}/{}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        start,
+                        content.len() - 1,
+                        content.len(),
+                        content.len() - start
+                    ),
+                    None => format!(
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        content.len()
+                    ),
+                };
+                let body = &content[start.unwrap_or(0).min(content.len())..];
+                let _ = stream.write_all(header.as_bytes()).await;
+                let _ = stream.write_all(body).await;
+            }
+        });
+        (format!("http://{}/file", address), ranges)
+    }
+
+    #[tokio::test]
+    async fn download_checks_hash() {
+        let content = b"rust launcher lib".to_vec();
+        let (url, _ranges) = spawn_file_server(content.clone()).await;
+        let dir = std::env::temp_dir().join("rust_launcher_lib_download_checks_hash");
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("file").to_string_lossy().to_string();
+        let manager = DownloadManager::new(dir.to_string_lossy().to_string());
+
+        let mut element = DownloadElement {
+            url,
+            path: path.clone(),
+            dl_tries: 0,
+            hash: Some("0000000000000000000000000000000000000000".to_string()),
+            hash_algorithm: HashAlgorithm::Sha1,
+            size: Some(content.len() as u64),
+        };
+        assert!(manager.download_file(&element).await.is_err());
+        assert!(!std::path::Path::new(&path).exists());
+        assert!(!std::path::Path::new(&(path.clone() + ".part")).exists());
+
+        element.hash = Some(hex::encode(<sha1::Sha1 as sha1::Digest>::digest(&content)));
+        manager.download_file(&element).await.unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), content);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn repair_redownloads_broken_files() {
+        use crate::update::structs::mc_assets::AssetsRoot;
+
+        let content = b"rust launcher lib".to_vec();
+        let sha1 = hex::encode(<sha1::Sha1 as sha1::Digest>::digest(&content));
+        let (url, requests) = spawn_file_server(content.clone()).await;
+        let dir = std::env::temp_dir().join("rust_launcher_lib_repair");
+        let _ = std::fs::remove_dir_all(&dir);
+        let local_dir_path = dir.to_string_lossy().to_string() + std::path::MAIN_SEPARATOR_STR;
+        let library = |name: &str| serde_json::json!({"name": format!("test:{}:1", name),
+            "downloads": {"artifact": {"path": format!("{}.jar", name), "sha1": sha1, "size": content.len(), "url": url}}});
+        let libs_manifest = LibsRoot::parse_json(serde_json::json!({
+            "id": "test",
+            "mainClass": "Main",
+            "assetIndex": {"id": "test", "sha1": sha1, "size": content.len(), "totalSize": 0, "url": url},
+            "downloads": {"client": {"sha1": sha1, "size": content.len(), "url": url}},
+            "libraries": [library("valid"), library("missing"), library("corrupted")]
+        }).to_string()).unwrap();
+        let assets_manifest: AssetsRoot =
+            serde_json::from_value(serde_json::json!({"objects": {"icon.png": {"hash": sha1, "size": content.len()}}})).unwrap();
+
+        let write = |path: String, content: &[u8]| {
+            std::fs::create_dir_all(std::path::Path::new(&path).parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        let lib_path = |name: &str| crate::update::utils::get_lib_path(&local_dir_path, &format!("{}.jar", name));
+        write(lib_path("valid"), &content);
+        // same size, another content
+        write(lib_path("corrupted"), &b"RUST LAUNCHER LIB"[..]);
+        write(lib_path("old"), &content);
+        write(crate::update::utils::get_asset_path_from_hash(local_dir_path.clone(), &sha1).1, &content);
+        write(crate::update::utils::get_client_jar_path(&local_dir_path, "test"), &content);
+        write(crate::update::utils::get_asset_index_path(&local_dir_path, &url), &content);
+        write(crate::update::utils::get_java_path(&local_dir_path, "8"), b"java");
+        // another version installed in the same dir, its libs are not extra files
+        write(
+            crate::update::utils::get_version_json_path(&local_dir_path, "other"),
+            serde_json::json!({"id": "other", "mainClass": "Main", "assetIndex": {"id": "other", "sha1": "", "size": 0, "totalSize": 0, "url": ""},
+                "downloads": {"client": {"sha1": "", "size": 0, "url": ""}}, "libraries": [library("shared")]}).to_string().as_bytes(),
+        );
+        write(lib_path("shared"), &content);
+
+        let mut updater = Updater::new("test");
+        updater.set_local_dir_path(local_dir_path.clone());
+        updater.set_libs_manifest(Some(libs_manifest));
+        updater.set_assets_manifest(Some(assets_manifest));
+
+        let report = updater.repair(false).await.unwrap();
+        assert_eq!(report.missing, vec![lib_path("missing")]);
+        assert_eq!(report.corrupted, vec![lib_path("corrupted")]);
+        assert_eq!(report.extra, vec![lib_path("old")]);
+        assert!(requests.lock().unwrap().is_empty());
+
+        // only the two broken files are downloaded again
+        let report = updater.repair(true).await.unwrap();
+        assert!(report.failed.is_empty());
+        assert_eq!(requests.lock().unwrap().len(), 2);
+        assert_eq!(std::fs::read(lib_path("missing")).unwrap(), content);
+        assert_eq!(std::fs::read(lib_path("corrupted")).unwrap(), content);
+        assert!(updater.repair(false).await.unwrap().is_healthy());
+        // a file that couldn't be downloaded again is still broken
+        let report = crate::update::repair::RepairReport {
+            failed: vec![crate::update::downloads::FailedDownload {
+                element: DownloadElement::default(),
+                error: crate::error::LauncherError::JavaRuntimeMissing("java".to_string()),
+            }],
+            ..Default::default()
+        };
+        assert!(!report.is_healthy());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn download_resumes_part_file() {
+        let content: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+        let (url, ranges) = spawn_file_server(content.clone()).await;
+        let dir = std::env::temp_dir().join("rust_launcher_lib_download_resumes_part_file");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("file").to_string_lossy().to_string();
+        std::fs::write(path.clone() + ".part", &content[..40_000]).unwrap();
+
+        let manager = DownloadManager::new(dir.to_string_lossy().to_string());
+        let element = DownloadElement {
+            url,
+            path: path.clone(),
+            dl_tries: 0,
+            hash: Some(hex::encode(<sha1::Sha1 as sha1::Digest>::digest(&content))),
+            hash_algorithm: HashAlgorithm::Sha1,
+            size: Some(content.len() as u64),
+        };
+        manager.download_file(&element).await.unwrap();
+
+        assert_eq!(*ranges.lock().unwrap(), vec![Some(40_000)]);
+        assert_eq!(std::fs::read(&path).unwrap(), content);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn download_checks_sha256_on_resume() {
+        let content: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+        let (url, ranges) = spawn_file_server(content.clone()).await;
+        let dir = std::env::temp_dir().join("rust_launcher_lib_download_checks_sha256_on_resume");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("file").to_string_lossy().to_string();
+        // the start of the .part is corrupted, only the hash of the whole file can tell
+        std::fs::write(path.clone() + ".part", vec![0u8; 40_000]).unwrap();
+
+        let manager = DownloadManager::new(dir.to_string_lossy().to_string());
+        let element = DownloadElement {
+            url,
+            path: path.clone(),
+            dl_tries: 0,
+            hash: Some(hex::encode(<sha2::Sha256 as sha2::Digest>::digest(&content))),
+            hash_algorithm: HashAlgorithm::Sha256,
+            size: Some(content.len() as u64),
+        };
+        assert!(matches!(
+            manager.download_file(&element).await,
+            Err(crate::error::LauncherError::HashMismatch { .. })
+        ));
+        assert!(!std::path::Path::new(&(path.clone() + ".part")).exists());
+        assert!(!std::path::Path::new(&path).exists());
+
+        manager.download_file(&element).await.unwrap();
+        assert_eq!(*ranges.lock().unwrap(), vec![Some(40_000), None]);
+        assert_eq!(std::fs::read(&path).unwrap(), content);
+        // the file on the disk is checked with the sha256 too
+        assert!(crate::update::utils::check_file_hash_with(&path, element.hash.as_ref().unwrap(), HashAlgorithm::Sha256));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn download_verifies_existing_file() {
+        let content = b"the real content".to_vec();
+        let (url, _ranges) = spawn_file_server(content.clone()).await;
+        let dir = std::env::temp_dir().join("rust_launcher_lib_download_verifies_existing_file");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("file").to_string_lossy().to_string();
+        let tampered = b"the fake content".to_vec();
+        std::fs::write(&path, &tampered).unwrap();
+
+        let mut manager = DownloadManager::new(dir.to_string_lossy().to_string());
+        let element = DownloadElement {
+            url,
+            path: path.clone(),
+            dl_tries: 0,
+            hash: Some(hex::encode(<sha1::Sha1 as sha1::Digest>::digest(&content))),
+            hash_algorithm: HashAlgorithm::Sha1,
+            size: Some(content.len() as u64),
+        };
+
+        // same size: kept by the quick mode
+        manager.set_verify_mode(VerifyMode::Size);
+        manager.download_file(&element).await.unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), tampered);
+
+        manager.set_verify_mode(VerifyMode::Full);
+        manager.download_file(&element).await.unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), content);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn check_files() {
+        println!(
+            "{}",
+            get_java_zulu_dl_link(&reqwest::Client::new(), "11.0.11".to_string())
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn download_progress_events() {
+        use crate::update::downloads::{DownloadElement, DownloadManager};
+        use crate::update::progress::{progress_channel, ProgressEvent};
+
+        let content = b"rust launcher lib".to_vec();
+        let (url, _requests) = spawn_file_server(content.clone()).await;
+        let dir = std::env::temp_dir().join("rust_launcher_lib_download_progress_events");
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("file").to_string_lossy().to_string();
+        let (sender, mut receiver) = progress_channel();
+        let mut manager = DownloadManager::new(dir.to_string_lossy().to_string());
+        manager.set_progress_sender(Some(sender));
+
+        // no size in the manifest (like the java zip), the first try fails
+        let mut element = DownloadElement {
+            url,
+            path: path.clone(),
+            dl_tries: 0,
+            hash: Some("0000000000000000000000000000000000000000".to_string()),
+            hash_algorithm: HashAlgorithm::Sha1,
+            size: None,
+        };
+        assert!(manager.download_file(&element).await.is_err());
+        element.hash = Some(hex::encode(<sha1::Sha1 as sha1::Digest>::digest(&content)));
+        manager.download_file(&element).await.unwrap();
+        drop(manager);
+
+        let mut events = vec![];
+        while let Some(event) = receiver.recv().await {
+            events.push(event);
+        }
+        let started = events.iter().filter(|event| matches!(event, ProgressEvent::FileStarted { .. })).count();
+        assert_eq!(started, 2);
+        assert!(events.iter().any(|event| matches!(event, ProgressEvent::FileFailed { .. })));
+        assert!(events.iter().any(|event| matches!(event, ProgressEvent::FileProgress { size: Some(17), .. })));
+        // the size given by the server for the failed try isn't counted twice
+        let total = events.iter().rev().find(|event| matches!(event, ProgressEvent::TotalBytes { .. }));
+        assert_eq!(total, Some(&ProgressEvent::TotalBytes { known: 17, done: 17 }));
+        assert_eq!(events.last(), Some(&ProgressEvent::FileCompleted { path }));
+
+        // a big file received in many chunks doesn't send an event for each of them
+        let content = vec![7u8; 4 * 1024 * 1024];
+        let (url, _requests) = spawn_file_server(content.clone()).await;
+        let path = dir.join("big_file").to_string_lossy().to_string();
+        let (sender, mut receiver) = progress_channel();
+        let mut manager = DownloadManager::new(dir.to_string_lossy().to_string());
+        manager.set_progress_sender(Some(sender));
+        let element = DownloadElement {
+            url,
+            path: path.clone(),
+            dl_tries: 0,
+            hash: None,
+            hash_algorithm: HashAlgorithm::Sha1,
+            size: Some(content.len() as u64),
+        };
+        let started = std::time::Instant::now();
+        manager.download_file(&element).await.unwrap();
+        let max_events = 3 + (started.elapsed().as_millis() / 100) as usize;
+        drop(manager);
+
+        let mut events = vec![];
+        while let Some(event) = receiver.recv().await {
+            events.push(event);
+        }
+        let progress: Vec<_> = events.iter().filter(|event| matches!(event, ProgressEvent::FileProgress { .. })).collect();
+        let totals = events.iter().filter(|event| matches!(event, ProgressEvent::TotalBytes { .. })).count();
+        assert!(progress.len() <= max_events, "{} progress events", progress.len());
+        assert!(totals <= max_events + 2, "{} total events", totals);
+        // the end of the file is always sent
+        assert_eq!(
+            progress.last(),
+            Some(&&ProgressEvent::FileProgress { path, downloaded: content.len() as u64, size: Some(content.len() as u64) })
+        );
+        let size = content.len() as u64;
+        let total = events.iter().rev().find(|event| matches!(event, ProgressEvent::TotalBytes { .. }));
+        // the size given by the manifest is only known by a phase
+        assert_eq!(total, Some(&ProgressEvent::TotalBytes { known: 0, done: size }));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn download_limits() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use crate::update::downloads::{DownloadElement, DownloadManager};
+
+        // each request is answered after a while, the most requests running at the same time are counted
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let (server_running, server_peak) = (running.clone(), peak.clone());
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let (running, peak) = (server_running.clone(), server_peak.clone());
+                tokio::spawn(async move {
+                    let _ = stream.read(&mut [0u8; 4096]).await;
+                    peak.fetch_max(running.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 1\r\nConnection: close\r\n\r\nx").await;
+                });
+            }
+        });
+
+        let dir = std::env::temp_dir().join("rust_launcher_lib_download_limits");
+        // peak of the downloads of 4 files from each host
+        let download = |hosts: &'static [&'static str], max_concurrent: usize, max_per_host: usize| {
+            let (dir, peak) = (dir.clone(), peak.clone());
+            async move {
+                let _ = std::fs::remove_dir_all(&dir);
+                peak.store(0, Ordering::SeqCst);
+                let mut manager = DownloadManager::new(dir.to_string_lossy().to_string());
+                manager.set_max_concurrent(max_concurrent);
+                manager.set_max_per_host(max_per_host);
+                let elements = hosts
+                    .iter()
+                    .flat_map(|host| {
+                        let dir = dir.clone();
+                        (0..4).map(move |index| DownloadElement {
+                            url: format!("http://{}:{}/{}", host, port, index),
+                            path: dir.join(format!("{}-{}", host, index)).to_string_lossy().to_string(),
+                            dl_tries: 0,
+                            hash: None,
+                            hash_algorithm: HashAlgorithm::Sha1,
+                            size: Some(1),
+                        })
+                    })
+                    .collect();
+                manager.download_phase(crate::update::progress::Phase::Libraries, elements).await;
+                assert!(manager.fails().is_empty());
+                peak.load(Ordering::SeqCst)
+            }
+        };
+
+        assert_eq!(download(&["127.0.0.1"], 8, 1).await, 1);
+        assert_eq!(download(&["127.0.0.1"], 8, 2).await, 2);
+        assert_eq!(download(&["127.0.0.1"], 1, 8).await, 1);
+        // the limit is for each host, the downloads of two hosts run side by side
+        assert_eq!(download(&["127.0.0.1", "localhost"], 8, 1).await, 2);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn launch_test() {
         let mut updater = Updater::new("1.8.9");
         updater.set_relative_local_dir_path(".banane");
-        updater.install_files();
+        if let Err(error) = updater.install_files().await {
+            println!("{}", error);
+        }
 
         let launcher =
             launch::GameLauncher::new("1.8.9".to_string(), ".banane".to_string(), vec![], vec![]);
-        if let Err(error) = launcher.launch("", "knightmar67") {
+        let session = Session::from_offline(&OfflineAccount::new("knightmar67").unwrap());
+        if let Err(error) = launcher.launch(&session).await {
             println!("{}", error);
         };
     }
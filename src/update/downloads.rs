@@ -1,40 +1,121 @@
 use std::fs;
 use std::collections::HashMap;
-use std::io::Cursor;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
-use reqwest::Client;
+use futures::StreamExt;
+use reqwest::header::{CONTENT_RANGE, RANGE};
+use reqwest::{Client, Response, StatusCode};
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 
+use crate::error::{LauncherError, Result};
 use crate::update::java;
+use crate::update::progress::{Phase, ProgressEvent, ProgressReporter, PROGRESS_INTERVAL};
+use crate::update::retry::RetryPolicy;
 use crate::update::structs::mc_assets::Object;
 use crate::update::structs::mc_libs::{Library, LibsRoot};
+use crate::update::structs::rules::Environment;
 use crate::update::utils::{
-    Directory, get_asset_path_from_hash, get_file_name_from_url, get_lib_path_from_url,
+    check_file_hash_with, get_asset_index_path, get_asset_path_from_hash,
+    get_client_jar_path, get_java_path, get_log_config_path, get_lib_path, get_part_path, get_runtime_path,
+    run_blocking, FileHasher, HashAlgorithm,
 };
 
 // struct that describe an element to download
-#[derive(Clone, PartialEq)]
+#[derive(Clone, Default, PartialEq)]
 pub struct DownloadElement {
     pub url: String,
     pub path: String,
     pub dl_tries: u8,
     pub hash: Option<String>,
+    // sha1 for the files of mojang
+    pub hash_algorithm: HashAlgorithm,
+    pub size: Option<u64>,
+}
+
+// a file that couldn't be downloaded, with the error of its last try
+pub struct FailedDownload {
+    pub element: DownloadElement,
+    pub error: LauncherError,
+}
+
+// result of an installation, listing the files that permanently failed
+#[derive(Default)]
+pub struct InstallReport {
+    pub failed: Vec<FailedDownload>,
+}
+
+impl InstallReport {
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+// how the files already on the disk are checked before being skipped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VerifyMode {
+    // any existing file is kept, even if it is truncated
+    Exists,
+    // existing files are kept if their size matches the manifest (fast, for quick launches)
+    Size,
+    // existing files are kept if their size and sha1 match the manifest
+    #[default]
+    Full,
+}
+
+pub const DEFAULT_MAX_CONCURRENT: usize = 32;
+pub const DEFAULT_MAX_PER_HOST: usize = 8;
+
+// limits the number of downloads running at the same time on each host
+#[derive(Clone, Default)]
+struct HostLimiter {
+    semaphores: Arc<Mutex<HashMap<String, Arc<Semaphore>>>>,
+}
+
+impl HostLimiter {
+    async fn acquire(&self, url: &str, max_per_host: usize) -> Option<OwnedSemaphorePermit> {
+        let host = reqwest::Url::parse(url).ok()?.host_str()?.to_string();
+        let semaphore = self
+            .semaphores
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .entry(host)
+            .or_insert_with(|| Arc::new(Semaphore::new(max_per_host.max(1))))
+            .clone();
+        semaphore.acquire_owned().await.ok()
+    }
 }
 
 // base struct that is responsible to manage the downloads
-#[derive(Clone)]
 pub struct DownloadManager {
-    client: Arc<Client>,
-    fails: Vec<DownloadElement>,
+    client: Client,
+    fails: Vec<FailedDownload>,
     local_dir_path: String,
+    progress: ProgressReporter,
+    retry_policy: RetryPolicy,
+    verify_mode: VerifyMode,
+    environment: Environment,
+    max_concurrent: usize,
+    max_per_host: usize,
+    host_limiter: HostLimiter,
 }
 
 impl Default for DownloadManager {
     fn default() -> Self {
         Self {
-            client: Arc::new(Client::new()),
+            client: Client::new(),
             fails: vec![],
             local_dir_path: String::new(),
+            progress: ProgressReporter::default(),
+            retry_policy: RetryPolicy::default(),
+            verify_mode: VerifyMode::default(),
+            environment: Environment::current(),
+            max_concurrent: DEFAULT_MAX_CONCURRENT,
+            max_per_host: DEFAULT_MAX_PER_HOST,
+            host_limiter: HostLimiter::default(),
         }
     }
 }
@@ -42,145 +123,406 @@ impl Default for DownloadManager {
 impl DownloadManager {
     // download of the libs
     pub async fn download_libs(&mut self, libs: Vec<Library>) {
-        println!("Downloading libs");
-        for lib in libs {
-            let download_path = get_lib_path_from_url(self.local_dir_path.clone(), &lib.downloads.artifact.path);
-
-            if let Err(_result) = DownloadManager::download_file(
-                self.client.clone(),
-                &lib.downloads.artifact.url,
-                download_path.clone(),
-                &Some(lib.downloads.artifact.sha1.clone()),
-            ).await
-            {
-                self.fails.push(DownloadElement {
-                    url: lib.downloads.artifact.url,
-                    path: download_path,
-                    dl_tries: 0,
-                    hash: Some(lib.downloads.artifact.sha1),
-                })
-            }
-        }
+        let files_to_dl = self.libs_elements(&libs);
+        self.download_phase(Phase::Libraries, files_to_dl).await;
     }
 
     // download of the assets
     pub async fn download_assets(&mut self, assets: &HashMap<String, Object>) {
-        println!("Downloading assets");
-        for asset in assets {
-            let hash = asset.1.hash();
-            let url = &("https://resources.download.minecraft.net/".to_string() + hash[0..2].to_string().as_str() + std::path::MAIN_SEPARATOR_STR + hash);
-
-            let download_path = get_asset_path_from_hash(self.local_dir_path.clone(), hash);
-
-            if let Err(_result) = Self::download_file(
-                self.client.clone(),
-                url,
-                download_path.1.to_string(),
-                &Some(hash.to_string()),
-            ).await
-            {
-                self.fails.push(DownloadElement {
-                    url: url.to_string(),
-                    path: download_path.1,
+        let files_to_dl = self.assets_elements(assets);
+        self.download_phase(Phase::Assets, files_to_dl).await;
+    }
+
+    // the artifact of each lib allowed by its rules, plus its natives jar for the os of the environment
+    pub(crate) fn libs_elements(&self, libs: &[Library]) -> Vec<DownloadElement> {
+        libs.iter()
+            .filter(|lib| lib.is_allowed(&self.environment))
+            .flat_map(|lib| {
+                lib.main_artifact()
+                    .into_iter()
+                    .chain(lib.native_artifact(&self.environment.os_name))
+            })
+            .map(|artifact| DownloadElement {
+                path: get_lib_path(&self.local_dir_path, &artifact.path),
+                url: artifact.url,
+                dl_tries: 0,
+                // the libs only hosted on a maven repository don't give their hash nor their size
+                hash: Some(artifact.sha1).filter(|sha1| !sha1.is_empty()),
+                hash_algorithm: HashAlgorithm::Sha1,
+                size: u64::try_from(artifact.size).ok().filter(|size| *size > 0),
+            })
+            .collect()
+    }
+
+    pub(crate) fn assets_elements(&self, assets: &HashMap<String, Object>) -> Vec<DownloadElement> {
+        assets
+            .values()
+            .map(|asset| {
+                let hash = asset.hash();
+                DownloadElement {
+                    url: "https://resources.download.minecraft.net/".to_string() + &hash[0..2] + "/" + hash,
+                    path: get_asset_path_from_hash(self.local_dir_path.clone(), hash).1,
                     dl_tries: 0,
                     hash: Some(hash.to_string()),
-                })
+                    hash_algorithm: HashAlgorithm::Sha1,
+                    size: u64::try_from(asset.size()).ok(),
+                }
+            })
+            .collect()
+    }
+
+    // download + unzip of the java runtime in its own dir, next to the runtimes of the other java versions
+    pub async fn download_java(&self, java_version: String) -> Result<()> {
+        self.progress.emit(ProgressEvent::PhaseStarted(Phase::Java));
+
+        let runtime_path = get_runtime_path(&self.local_dir_path, &java_version);
+        let java_path = runtime_path.clone() + "java.zip";
+        if !std::path::Path::new(&get_java_path(&self.local_dir_path, &java_version)).exists() {
+            let package = java::get_java_zulu_package(&self.client, java_version).await?;
+
+            let element = DownloadElement {
+                url: package.download_url,
+                path: java_path.clone(),
+                dl_tries: 0,
+                hash: Some(package.sha256_hash).filter(|hash| !hash.is_empty()),
+                hash_algorithm: HashAlgorithm::Sha256,
+                size: Some(package.size).filter(|size| *size > 0),
+            };
+            if let Err(error) = self.download_file(&element).await {
+                if let Some(fail) = self.retry(FailedDownload { element, error }).await {
+                    return Err(fail.error);
+                }
             }
+
+            run_blocking(move || {
+                let archive = fs::File::open(&java_path).map_err(|e| LauncherError::io(&java_path, e))?;
+                zip_extract::extract(archive, runtime_path.as_ref(), true).map_err(|e| {
+                    LauncherError::JavaRuntimeMissing(format!(
+                        "could not extract {} into {}: {}",
+                        java_path, runtime_path, e
+                    ))
+                })?;
+
+                fs::remove_file(&java_path).map_err(|e| LauncherError::io(&java_path, e))
+            })
+            .await?;
         }
+
+        self.progress.emit(ProgressEvent::PhaseFinished(Phase::Java));
+        Ok(())
     }
 
-    // download + unzip of the java runtime in the correct dir
-    pub async fn download_java(&self, java_version: String) {
-        println!("Downloading java");
+    // download of the jar of the version (versions/<id>/<id>.jar) + asset index + log4j config
+    pub async fn download_game_files(&mut self, root: LibsRoot) {
+        let files_to_dl = self.game_files_elements(&root);
+        self.download_phase(Phase::GameFiles, files_to_dl).await;
+    }
 
-        let java_path = self.local_dir_path.to_string() + &*Directory::Runtime.as_str() + "java.zip";
-        let java_url = java::get_java_zulu_dl_link(java_version).await.unwrap();
-        if std::path::Path::new(
-            &(self.local_dir_path.to_string() + &(Directory::Runtime.as_str() + "bin")),
-        ).exists()
-        {
-            println!("File already exists: {}", java_path);
-            return;
-        } else {
-            println!("Downloading: {}", java_path);
+    pub(crate) fn game_files_elements(&self, root: &LibsRoot) -> Vec<DownloadElement> {
+        let mut elements = vec![
+            DownloadElement {
+                url: root.downloads.client.url.clone(),
+                path: get_client_jar_path(&self.local_dir_path, &root.id),
+                dl_tries: 0,
+                hash: Some(root.downloads.client.sha1.clone()),
+                hash_algorithm: HashAlgorithm::Sha1,
+                size: u64::try_from(root.downloads.client.size).ok(),
+            },
+            DownloadElement {
+                url: root.asset_index.url.clone(),
+                path: get_asset_index_path(&self.local_dir_path, &root.asset_index.url),
+                dl_tries: 0,
+                hash: Some(root.asset_index.sha1.clone()),
+                hash_algorithm: HashAlgorithm::Sha1,
+                size: u64::try_from(root.asset_index.size).ok(),
+            },
+        ];
+
+        // the versions before 1.7 don't have a log config
+        if let Some(logging) = root.logging.as_ref().and_then(|logging| logging.client.as_ref()) {
+            elements.push(DownloadElement {
+                url: logging.file.url.clone(),
+                path: get_log_config_path(&self.local_dir_path, &logging.file.id),
+                dl_tries: 0,
+                hash: Some(logging.file.sha1.clone()),
+                hash_algorithm: HashAlgorithm::Sha1,
+                size: u64::try_from(logging.file.size).ok(),
+            });
         }
+        elements
+    }
+
+    // function that takes the failed downloads of the other download functions, and re-dl the fills that had errors
+    // the files that still fail once the retry policy is exhausted are returned
+    pub async fn download_fails(&mut self) -> Vec<FailedDownload> {
+        self.progress.emit(ProgressEvent::PhaseStarted(Phase::Retries));
+        let current_fails = std::mem::take(&mut self.fails);
+        let this = &*self;
+        let permanent_fails = futures::stream::iter(current_fails)
+            .map(|fail| this.retry(fail))
+            .buffer_unordered(self.max_concurrent.max(1))
+            .filter_map(|fail| async move { fail })
+            .collect()
+            .await;
+        self.progress.emit(ProgressEvent::PhaseFinished(Phase::Retries));
+        permanent_fails
+    }
 
-        DownloadManager::download_file(
-            Arc::clone(&self.client),
-            java_url.as_str(),
-            java_path.clone(),
-            &None,
-        ).await.expect("TODO: panic message");
+    // download a failed file again until it works, or until the error is fatal / the tries are exhausted
+    async fn retry(&self, mut fail: FailedDownload) -> Option<FailedDownload> {
+        loop {
+            let attempts = fail.element.dl_tries.saturating_add(1);
+            if !fail.error.is_retryable() || !self.retry_policy.can_retry(attempts) {
+                return Some(fail);
+            }
 
+            fail.element.dl_tries = attempts;
+            tokio::time::sleep(self.retry_policy.delay(attempts)).await;
+            self.progress.emit(ProgressEvent::Retry {
+                path: fail.element.path.clone(),
+                attempt: attempts,
+            });
 
-        if zip_extract::extract(
-            Cursor::new(fs::read(&java_path).unwrap()),
-            (self.local_dir_path.clone() + &*Directory::Runtime.as_str()).as_ref(),
-            true,
-        ).is_err()
-        {
-            return;
+            match self.download_file(&fail.element).await {
+                Ok(()) => return None,
+                Err(error) => fail.error = error,
+            }
         }
+    }
+
+    // download all the files of a phase, keeping the failed ones for download_fails
+    pub(crate) async fn download_phase(&mut self, phase: Phase, files_to_dl: Vec<DownloadElement>) {
+        self.progress.emit(ProgressEvent::PhaseStarted(phase));
+        self.progress
+            .add_known_bytes(files_to_dl.iter().filter_map(|file| file.size).sum());
 
-        if fs::remove_file(java_path.clone()).is_err() {}
+        let fails = self.download_all(files_to_dl).await;
+        self.fails.extend(fails);
+        // the files already on the disk only add their bytes
+        self.progress.emit_total();
+        self.progress.emit(ProgressEvent::PhaseFinished(phase));
     }
 
-    // download of the client.jar + asset index
-    pub async fn download_game_files(&mut self, root: LibsRoot) {
-        let mut files_to_dl: Vec<DownloadElement> = vec![];
+    // download the files concurrently (max_concurrent at a time), and return the ones that failed
+    async fn download_all(&self, files_to_dl: Vec<DownloadElement>) -> Vec<FailedDownload> {
+        futures::stream::iter(files_to_dl)
+            .map(|element| async move {
+                match self.download_file(&element).await {
+                    Ok(()) => None,
+                    Err(error) => Some(FailedDownload { element, error }),
+                }
+            })
+            .buffer_unordered(self.max_concurrent.max(1))
+            .filter_map(|fail| async move { fail })
+            .collect()
+            .await
+    }
 
-        println!("Downloading game files");
-        let client_path = self.local_dir_path.to_string() + "client.jar";
-        let client_url = root.client.url.clone();
+    // function that download a file, if it is not existing (or invalid), and then check its hash
+    pub(crate) async fn download_file(&self, element: &DownloadElement) -> Result<()> {
+        if self.is_existing_file_valid(element).await {
+            self.progress.add_done_bytes(element.size.unwrap_or(0));
+            return Ok(());
+        }
 
-        files_to_dl.push(DownloadElement {
-            url: client_url,
-            path: client_path,
-            dl_tries: 0,
-            hash: Some(root.client.sha1),
-        });
+        // wait for a free slot on the host of the file
+        let _permit = self.host_limiter.acquire(&element.url, self.max_per_host).await;
 
-        files_to_dl.push(DownloadElement {
-            url: root.asset_index.url.clone(),
-            path: self.local_dir_path.clone() + &Directory::Indexes.as_str() + &get_file_name_from_url(root.asset_index.url.as_str()),
-            dl_tries: 0,
-            hash: Some(root.asset_index.sha1),
+        self.progress.emit(ProgressEvent::FileStarted {
+            url: element.url.clone(),
+            path: element.path.clone(),
+            size: element.size,
         });
 
-        for file in files_to_dl {
-            if let Err(_result) = Self::download_file(
-                self.client.clone(),
-                file.url.as_str(),
-                file.path.clone(),
-                &file.hash,
-            ).await
-            {
-                self.fails.push(file);
-            };
+        let mut downloaded = 0;
+        let mut discovered_size = 0;
+        match self.fetch_file(element, &mut downloaded, &mut discovered_size).await {
+            Ok(()) => {
+                self.progress.emit_total();
+                self.progress.emit(ProgressEvent::FileCompleted {
+                    path: element.path.clone(),
+                });
+                Ok(())
+            }
+            Err(error) => {
+                self.progress.remove_done_bytes(downloaded);
+                self.progress.remove_known_bytes(discovered_size);
+                self.progress.emit(ProgressEvent::FileFailed {
+                    path: element.path.clone(),
+                    error: error.to_string(),
+                });
+                Err(error)
+            }
         }
     }
 
-    // function that takes the failed downloads of the other download functions, and re-dl the fills that had errors
-    pub async fn download_fails(&mut self) {
-        while !self.fails.is_empty() {
-            let current_fails = std::mem::take(&mut self.fails);
-            for fail in current_fails {
-                if Self::download_file(
-                    self.client.clone(),
-                    &fail.url,
-                    fail.path.clone(),
-                    &fail.hash,
-                ).await.is_err()
-                {
-                    self.fails.push(DownloadElement {
-                        url: fail.url,
-                        path: fail.path,
-                        dl_tries: fail.dl_tries + 1,
-                        hash: fail.hash,
-                    });
-                }
+    // stream the file chunk by chunk into a .part file, counting the bytes received in downloaded
+    // and the size given by the server (when the manifest doesn't have it) in discovered_size
+    // an existing .part file is resumed with a Range request when the server supports it
+    // the .part file is renamed to the real path only once its hash is checked
+    async fn fetch_file(&self, element: &DownloadElement, downloaded: &mut u64, discovered_size: &mut u64) -> Result<()> {
+        let url = element.url.as_str();
+        let path = element.path.as_str();
+        let part_path = get_part_path(path);
+
+        let mut resume_from = self.get_partial_length(element, &part_path).await;
+        let mut response = self.send_request(url, resume_from).await?;
+        if resume_from > 0 && !is_resumed_response(&response, resume_from) {
+            // the server ignored the range (200 with the full file) or refused it: restart from zero
+            if response.status() != StatusCode::OK {
+                response = self.send_request(url, 0).await?;
+            }
+            resume_from = 0;
+        }
+
+        let size = element
+            .size
+            .or(response.content_length().map(|length| length + resume_from));
+        if element.size.is_none() {
+            *discovered_size = size.unwrap_or(0);
+            self.progress.add_known_bytes(*discovered_size);
+        }
+
+        // Create the directories leading up to the file
+        if let Some(parent_dir) = std::path::Path::new(path).parent() {
+            tokio::fs::create_dir_all(parent_dir)
+                .await
+                .map_err(|e| LauncherError::io(parent_dir.display(), e))?;
+        }
+
+        // Create (or reopen) the async file and write the chunks into it, hashing them on the fly
+        let mut hasher = FileHasher::new(element.hash_algorithm);
+        let mut file = if resume_from > 0 {
+            hash_partial_file(&part_path, &mut hasher).await?;
+            tokio::fs::OpenOptions::new()
+                .append(true)
+                .open(&part_path)
+                .await
+                .map_err(|e| LauncherError::io(&part_path, e))?
+        } else {
+            File::create(&part_path)
+                .await
+                .map_err(|e| LauncherError::io(&part_path, e))?
+        };
+        *downloaded = resume_from;
+        self.progress.add_done_bytes(resume_from);
+        let mut last_progress: Option<(Instant, u64)> = None;
+
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| LauncherError::network(url, e))?
+        {
+            file.write_all(&chunk)
+                .await
+                .map_err(|e| LauncherError::io(&part_path, e))?;
+            hasher.update(&chunk);
+
+            *downloaded += chunk.len() as u64;
+            self.progress.add_done_bytes(chunk.len() as u64);
+            if last_progress.is_none_or(|(sent_at, _bytes)| sent_at.elapsed() >= PROGRESS_INTERVAL) {
+                self.emit_file_progress(path, *downloaded, size);
+                last_progress = Some((Instant::now(), *downloaded));
+            }
+        }
+        // the last chunks are always sent
+        if last_progress.is_none_or(|(_sent_at, bytes)| bytes != *downloaded) {
+            self.emit_file_progress(path, *downloaded, size);
+        }
+        file.flush()
+            .await
+            .map_err(|e| LauncherError::io(&part_path, e))?;
+        drop(file);
+
+        if let Some(hash) = &element.hash {
+            let found = hasher.finalize();
+            if &found != hash {
+                // a corrupted file is never kept
+                tokio::fs::remove_file(&part_path)
+                    .await
+                    .map_err(|e| LauncherError::io(&part_path, e))?;
+                return Err(LauncherError::HashMismatch {
+                    path: path.to_string(),
+                    expected: hash.to_string(),
+                    found,
+                });
+            }
+        }
+
+        tokio::fs::rename(&part_path, path)
+            .await
+            .map_err(|e| LauncherError::io(path, e))
+    }
+
+    fn emit_file_progress(&self, path: &str, downloaded: u64, size: Option<u64>) {
+        self.progress.emit(ProgressEvent::FileProgress {
+            path: path.to_string(),
+            downloaded,
+            size,
+        });
+    }
+
+    // check the file already at the path of the element, according to the verify mode
+    async fn is_existing_file_valid(&self, element: &DownloadElement) -> bool {
+        let metadata = match tokio::fs::metadata(&element.path).await {
+            Ok(metadata) => metadata,
+            Err(_e) => return false,
+        };
+        if self.verify_mode == VerifyMode::Exists {
+            return true;
+        }
+
+        if element.size.is_some_and(|size| size != metadata.len()) {
+            return false;
+        }
+        if self.verify_mode == VerifyMode::Size {
+            return true;
+        }
+
+        match &element.hash {
+            Some(hash) => {
+                let path = element.path.clone();
+                let (hash, algorithm) = (hash.clone(), element.hash_algorithm);
+                tokio::task::spawn_blocking(move || check_file_hash_with(&path, &hash, algorithm))
+                    .await
+                    .unwrap_or(false)
             }
+            None => true,
+        }
+    }
+
+    // size of the .part file left by an interrupted download, 0 when there is nothing to resume
+    async fn get_partial_length(&self, element: &DownloadElement, part_path: &str) -> u64 {
+        let length = match tokio::fs::metadata(part_path).await {
+            Ok(metadata) => metadata.len(),
+            Err(_e) => return 0,
+        };
+
+        // a complete .part would have been renamed, so a bigger one is garbage
+        if element.size.is_some_and(|size| length >= size) {
+            let _ = tokio::fs::remove_file(part_path).await;
+            return 0;
+        }
+        length
+    }
+
+    // send a GET request, asking for the end of the file only when resume_from is not 0
+    async fn send_request(&self, url: &str, resume_from: u64) -> Result<Response> {
+        let mut request = self.client.get(url);
+        if resume_from > 0 {
+            request = request.header(RANGE, format!("bytes={}-", resume_from));
         }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| LauncherError::network(url, e))?;
+        if resume_from > 0 && response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+            return Ok(response);
+        }
+        response
+            .error_for_status()
+            .map_err(|e| LauncherError::network(url, e))
     }
 
     pub fn new(path: String) -> Self {
@@ -190,7 +532,93 @@ impl DownloadManager {
         }
     }
 
-    pub fn fails(&self) -> &Vec<DownloadElement> {
+    // create a manager that shares the http client of its caller
+    pub fn with_client(path: String, client: Client) -> Self {
+        Self {
+            client,
+            local_dir_path: path,
+            ..Self::default()
+        }
+    }
+
+    // send the progress of the downloads to the given channel
+    pub fn set_progress_sender(&mut self, sender: Option<UnboundedSender<ProgressEvent>>) {
+        self.progress = ProgressReporter::new(sender);
+    }
+
+    // number of files downloaded at the same time
+    pub fn set_max_concurrent(&mut self, max_concurrent: usize) {
+        self.max_concurrent = max_concurrent;
+    }
+
+    // number of files downloaded at the same time from a single host (resources.download.minecraft.net, ...)
+    pub fn set_max_per_host(&mut self, max_per_host: usize) {
+        self.max_per_host = max_per_host;
+        self.host_limiter = HostLimiter::default();
+    }
+
+    pub fn max_concurrent(&self) -> usize {
+        self.max_concurrent
+    }
+
+    pub fn max_per_host(&self) -> usize {
+        self.max_per_host
+    }
+
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
+
+    pub fn set_verify_mode(&mut self, verify_mode: VerifyMode) {
+        self.verify_mode = verify_mode;
+    }
+
+    pub fn verify_mode(&self) -> VerifyMode {
+        self.verify_mode
+    }
+
+    // os and features the rules of the libs are evaluated against
+    pub fn set_environment(&mut self, environment: Environment) {
+        self.environment = environment;
+    }
+
+    pub fn environment(&self) -> &Environment {
+        &self.environment
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    pub fn fails(&self) -> &Vec<FailedDownload> {
         &self.fails
     }
 }
+
+// the server answered a range request with the end of the file, starting where we asked
+fn is_resumed_response(response: &Response, resume_from: u64) -> bool {
+    response.status() == StatusCode::PARTIAL_CONTENT
+        && response
+            .headers()
+            .get(CONTENT_RANGE)
+            .and_then(|range| range.to_str().ok())
+            .is_some_and(|range| range.starts_with(&format!("bytes {}-", resume_from)))
+}
+
+// feed the bytes already downloaded in a .part file to the hasher
+async fn hash_partial_file(part_path: &str, hasher: &mut FileHasher) -> Result<()> {
+    let mut file = File::open(part_path)
+        .await
+        .map_err(|e| LauncherError::io(part_path, e))?;
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .await
+            .map_err(|e| LauncherError::io(part_path, e))?;
+        if read == 0 {
+            return Ok(());
+        }
+        hasher.update(&buffer[..read]);
+    }
+}
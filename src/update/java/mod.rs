@@ -1,34 +1,44 @@
 use std::env;
-use crate::update::java::structs::ZuluRoot;
+
+use reqwest::Client;
+
+use crate::error::{LauncherError, Result};
+use crate::update::java::structs::{PackageDetails, ZuluRoot};
 
 mod structs;
 
+const ZULU_PACKAGES_URL: &str = "https://api.azul.com/metadata/v1/zulu/packages/";
+
 // get the java runtime link to install in $BASE_DIR/runtime
-pub async fn get_java_zulu_dl_link(version: String) -> Result<String, Box<dyn std::error::Error>> {
-    let client = reqwest::Client::new();
-    let mut versions = None;
+pub async fn get_java_zulu_dl_link(client: &Client, version: String) -> Result<String> {
+    Ok(get_java_zulu_package(client, version).await?.download_url)
+}
+
+// zip of the java runtime to install, with its sha256 and its size
+pub async fn get_java_zulu_package(client: &Client, version: String) -> Result<PackageDetails> {
+    let url = format!("{}?java_version={}&os={}&arch={}&java_package_type=jdk&javafx_bundled=false&release_status=ga", ZULU_PACKAGES_URL, version, env::consts::OS, "x86_64");
+    let versions: ZuluRoot = get_json(client, &url).await?;
 
-    if let Ok(res) = client
-        .get(format!("https://api.azul.com/metadata/v1/zulu/packages/?java_version={}&os={}&arch={}&java_package_type=jdk&javafx_bundled=false&release_status=ga", version, env::consts::OS, "x86_64"))
+    let package = versions
+        .iter()
+        .find(|package| package.name.ends_with(".zip"))
+        .ok_or_else(|| LauncherError::JavaRuntimeMissing(format!("no zulu package found for java {}", version)))?;
+
+    // the list doesn't give the checksum of the packages
+    get_json(client, &(ZULU_PACKAGES_URL.to_string() + &package.package_uuid)).await
+}
+
+async fn get_json<T: serde::de::DeserializeOwned>(client: &Client, url: &str) -> Result<T> {
+    let text = client
+        .get(url)
         .header("accept", "application/json")
         .send()
         .await
-    {
-        if let Ok(text) = res.text().await {
-            if let Ok(parse) = serde_json::from_str::<ZuluRoot>(&text) {
-                versions = Some(parse)
-            }
-        }
-    }
-
-    if let Some(versions) = versions {
-        for version in &versions {
-            if version.name.ends_with(".zip") {
-                return Ok(version.download_url.clone());
-            }
-        }
-
-        return Err("No versions found".into());
-    }
-    return Err("Error getting versions".into());
+        .and_then(|res| res.error_for_status())
+        .map_err(|e| LauncherError::network(url, e))?
+        .text()
+        .await
+        .map_err(|e| LauncherError::network(url, e))?;
+
+    serde_json::from_str::<T>(&text).map_err(|e| LauncherError::manifest("zulu", e))
 }
@@ -16,3 +16,13 @@ pub struct Version {
     pub distro_version: Vec<i64>,
     pub availability_type: String,
 }
+
+// details of a package, with its checksum (from /packages/<package_uuid>)
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackageDetails {
+    pub package_uuid: String,
+    pub name: String,
+    pub download_url: String,
+    pub sha256_hash: String,
+    pub size: u64,
+}
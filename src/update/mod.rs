@@ -1,179 +1,82 @@
-use std::error::Error;
-use std::sync::Arc;
-
 use reqwest::Client;
-use tokio::fs::File;
-use tokio::io::AsyncWriteExt;
 
-use crate::update::downloads::DownloadManager;
+use crate::error::{LauncherError, Result};
 use crate::update::structs::mc_assets::AssetsRoot;
-use crate::update::structs::mc_libs::LibsRoot;
-use crate::update::structs::mc_versions::{Version, Versions};
+use crate::update::structs::mc_versions::Versions;
 use crate::update::updater::Updater;
-use crate::update::utils::check_file_hash;
+use crate::update::utils::get_version_json_path;
 
 pub mod downloads;
 pub(crate) mod java;
+pub mod progress;
+pub mod repair;
+pub mod retry;
 pub mod structs;
 pub mod utils;
-pub(crate) mod updater;
-
+pub mod updater;
+
+// get the text of a json file from the Mojang api
+async fn get_json(client: &Client, url: &str) -> Result<String> {
+    client
+        .get(url)
+        .send()
+        .await
+        .and_then(|res| res.error_for_status())
+        .map_err(|e| LauncherError::network(url, e))?
+        .text()
+        .await
+        .map_err(|e| LauncherError::network(url, e))
+}
 
 impl Updater {
     // get the version list from the json
-    pub fn get_versions_list(&self) -> Option<Versions> {
-        let client = Client::new();
-        let mut versions = None;
+    pub async fn get_versions_list(&self) -> Result<Versions> {
+        let url = "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json";
+        let text = get_json(self.client(), url).await?;
 
-        let runtime = tokio::runtime::Runtime::new().unwrap();
-        runtime.block_on(async {
-            if let Ok(res) = client
-                .get("https://piston-meta.mojang.com/mc/game/version_manifest_v2.json")
-                .send()
-                .await
-            {
-                if let Ok(text) = res.text().await {
-                    if let Ok(parse) = serde_json::from_str(&text) {
-                        versions = Some(parse)
-                    }
-                }
-            }
-        });
-
-        versions
+        serde_json::from_str(&text).map_err(|e| LauncherError::manifest("versions", e))
     }
 
     // load the Updater instance fields libs_manifest and assets_manifest with the matching files for the good version of the game
-    pub fn update_files_list(&mut self) -> Result<(), Box<dyn Error>> {
-        let mut is_version_correct = false;
-        let mut version: Option<Version> = None;
-        match self.get_versions_list() {
-            None => {
-                return Err("No versions found".into());
-            }
-            Some(mc_versions) => {
-                for version_item in mc_versions.versions() {
-                    if version_item.id() == self.version() {
-                        is_version_correct = true;
-                        version = Some((*version_item).clone());
-                        break;
-                    }
-                }
-            }
-        }
-
-        if !is_version_correct {
-            println!("Version not found");
-            return Err("Version not found".into());
-        }
-
-        let client = Client::new();
-        let mut libs_manifest: Option<LibsRoot> = None;
-        let mut assets_manifest: Option<AssetsRoot> = None;
-
-        let runtime = tokio::runtime::Runtime::new().unwrap();
-        runtime.block_on(async {
-            match version {
-                None => {}
-                Some(version) => {
-                    if let Ok(res) = client.get(version.url()).send().await {
-                        if let Ok(text) = res.text().await {
-                            match LibsRoot::parse_json(text) {
-                                Ok(parse) => {
-                                    libs_manifest = Some(parse);
-                                    if let Ok(res) = client
-                                        .get(libs_manifest.clone().unwrap().asset_index.url)
-                                        .send()
-                                        .await
-                                    {
-                                        if let Ok(text) = res.text().await {
-                                            match serde_json::from_str::<AssetsRoot>(&text) {
-                                                Ok(parse) => {
-                                                    assets_manifest = Some(parse);
-                                                }
-                                                Err(e) => {
-                                                    println!(
-                                                        "Error parsing assets manifest: {}",
-                                                        e
-                                                    );
-                                                }
-                                            }
-                                        } else {
-                                            println!("Error getting files list");
-                                        }
-                                    } else {
-                                        println!("Error getting files list");
-                                    }
-                                }
-                                Err(e) => {
-                                    println!("Error parsing lib manifest: {}", e);
-                                }
-                            }
-                        } else {
-                            println!("Error getting files list");
-                        }
-                    } else {
-                        println!("Error getting files list");
-                    }
-                }
-            }
-        });
-
-        self.set_libs_manifest(libs_manifest);
-        self.set_assets_manifest(assets_manifest);
+    pub async fn update_files_list(&mut self) -> Result<()> {
+        let version = self
+            .get_versions_list()
+            .await?
+            .versions()
+            .iter()
+            .find(|version_item| version_item.id() == self.version())
+            .cloned()
+            .ok_or_else(|| LauncherError::VersionNotFound(self.version().to_string()))?;
+
+        self.set_version_json(get_json(self.client(), version.url()).await?)?;
+
+        let asset_index_url = &self.libs_manifest_or_err()?.asset_index.url;
+        let text = get_json(self.client(), asset_index_url).await?;
+        let assets_manifest = serde_json::from_str::<AssetsRoot>(&text)
+            .map_err(|e| LauncherError::manifest("assets", e))?;
+
+        self.set_assets_manifest(Some(assets_manifest));
         Ok(())
     }
-}
 
-impl DownloadManager {
-    // function that download a file, if it is not existing, and then check its hash
-    pub(crate) async fn download_file(
-        client: Arc<Client>,
-        url: &str,
-        path: String,
-        hash: &Option<String>,
-    ) -> Result<(), String> {
-        if std::path::Path::new(&path).exists() {
-            return Ok(());
+    // keep the json of the version next to its jar (versions/<id>/<id>.json), the launcher reads it from there
+    pub(crate) async fn save_version_json(&self) -> Result<()> {
+        let libs_manifest = self.libs_manifest_or_err()?;
+        let json_path = get_version_json_path(self.local_dir_path(), &libs_manifest.id);
+        // the text downloaded from Mojang is kept as is, the fields unknown to LibsRoot included
+        let json = match self.version_json() {
+            Some(json) => json.to_string(),
+            None => serde_json::to_string_pretty(libs_manifest)
+                .map_err(|e| LauncherError::manifest("version", e))?,
+        };
+
+        if let Some(parent) = std::path::Path::new(&json_path).parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| LauncherError::io(parent.display(), e))?;
         }
-
-        // Send a GET request
-        let response = client
-            .get(url)
-            .send()
+        tokio::fs::write(&json_path, json)
             .await
-            .map_err(|_| format!("Failed to send GET request to {}", url))?;
-
-        //println!("Downloading: {}", url);
-
-        // Get the bytes of the file
-        let bytes = response
-            .bytes()
-            .await
-            .map_err(|_| format!("Failed to get bytes from {}", url))?;
-
-        //println!("Downloaded {} bytes", bytes.len());
-
-        // Create the directories leading up to the file
-        let parent_dir = std::path::Path::new(path.as_str()).parent().unwrap();
-        tokio::fs::create_dir_all(parent_dir)
-            .await
-            .map_err(|_| format!("Failed to create directories for file at {}", path))?;
-
-        // Create a new async file and write the bytes into it
-        let mut file = File::create(path.as_str())
-            .await
-            .map_err(|_| format!("Failed to create file at {}", path))?;
-        file.write_all(&bytes)
-            .await
-            .map_err(|_| format!("Failed to write to file at {}", path))?;
-
-        if let Some(hash) = hash {
-            if !check_file_hash(path.as_str(), hash.as_str()) {
-                return Err(format!("Hash mismatch for file at {}", path));
-            }
-        }
-
-        Ok(())
+            .map_err(|e| LauncherError::io(&json_path, e))
     }
-}
\ No newline at end of file
+}
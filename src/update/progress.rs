@@ -0,0 +1,127 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+// time between two TotalBytes events (or two FileProgress events of a file), the chunks come much faster
+pub(crate) const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+// the different steps of an installation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Phase {
+    Libraries,
+    Assets,
+    Java,
+    GameFiles,
+    Retries,
+}
+
+// event sent to the launcher during an installation, to display its progress
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgressEvent {
+    PhaseStarted(Phase),
+    PhaseFinished(Phase),
+    // a file starts downloading, size is None when the manifest doesn't give it
+    FileStarted {
+        url: String,
+        path: String,
+        size: Option<u64>,
+    },
+    // bytes received for a file since the start of its download, sent at most every 100 ms
+    FileProgress {
+        path: String,
+        downloaded: u64,
+        size: Option<u64>,
+    },
+    FileCompleted {
+        path: String,
+    },
+    FileFailed {
+        path: String,
+        error: String,
+    },
+    // bytes of all the files known so far, and bytes already on the disk
+    // sent at most every 100 ms while the files download, and at the end of each file and phase
+    TotalBytes {
+        known: u64,
+        done: u64,
+    },
+    // a failed file is downloaded again, attempt starts at 1 for the first retry
+    Retry {
+        path: String,
+        attempt: u8,
+    },
+}
+
+// create a channel to give to Updater::set_progress_sender
+pub fn progress_channel() -> (UnboundedSender<ProgressEvent>, UnboundedReceiver<ProgressEvent>) {
+    unbounded_channel()
+}
+
+// sends the events to the launcher (if it listens) and keeps the byte counters
+#[derive(Clone, Default)]
+pub(crate) struct ProgressReporter {
+    sender: Option<UnboundedSender<ProgressEvent>>,
+    known_bytes: Arc<AtomicU64>,
+    done_bytes: Arc<AtomicU64>,
+    last_total: Arc<Mutex<Option<Instant>>>,
+}
+
+impl ProgressReporter {
+    pub fn new(sender: Option<UnboundedSender<ProgressEvent>>) -> Self {
+        Self {
+            sender,
+            ..Self::default()
+        }
+    }
+
+    pub fn emit(&self, event: ProgressEvent) {
+        if let Some(sender) = &self.sender {
+            // the launcher may have dropped the receiver, the install goes on anyway
+            let _ = sender.send(event);
+        }
+    }
+
+    pub fn add_known_bytes(&self, bytes: u64) {
+        self.known_bytes.fetch_add(bytes, Ordering::Relaxed);
+        self.emit_total();
+    }
+
+    // size of a failed download that wasn't in the manifest, it's counted again on the next try
+    pub fn remove_known_bytes(&self, bytes: u64) {
+        self.known_bytes.fetch_sub(bytes, Ordering::Relaxed);
+        self.emit_total();
+    }
+
+    // called for each chunk, the total is only sent when the last one is older than PROGRESS_INTERVAL
+    pub fn add_done_bytes(&self, bytes: u64) {
+        self.done_bytes.fetch_add(bytes, Ordering::Relaxed);
+        if self.sender.is_none() {
+            return;
+        }
+        let is_recent = self
+            .last_total
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .is_some_and(|last_total| last_total.elapsed() < PROGRESS_INTERVAL);
+        if !is_recent {
+            self.emit_total();
+        }
+    }
+
+    // bytes of a failed download, that will be received again on the next try
+    pub fn remove_done_bytes(&self, bytes: u64) {
+        self.done_bytes.fetch_sub(bytes, Ordering::Relaxed);
+        self.emit_total();
+    }
+
+    // send the total right away (at the end of a file or of a phase)
+    pub fn emit_total(&self) {
+        *self.last_total.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(Instant::now());
+        self.emit(ProgressEvent::TotalBytes {
+            known: self.known_bytes.load(Ordering::Relaxed),
+            done: self.done_bytes.load(Ordering::Relaxed),
+        });
+    }
+}
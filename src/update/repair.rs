@@ -0,0 +1,213 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use futures::StreamExt;
+
+use crate::error::Result;
+use crate::update::downloads::{DownloadElement, DownloadManager, FailedDownload, VerifyMode};
+use crate::update::progress::Phase;
+use crate::update::structs::mc_assets::AssetsRoot;
+use crate::update::structs::mc_libs::LibsRoot;
+use crate::update::updater::Updater;
+use crate::update::utils::{
+    check_file_hash_with, get_asset_index_path, get_java_path, get_version_json_path, list_files_recursive, migrate_flat_libraries,
+    run_blocking, Directory,
+};
+
+// state of the installation, as found by Updater::repair
+#[derive(Default)]
+pub struct RepairReport {
+    // files of the manifests that are not on the disk
+    pub missing: Vec<String>,
+    // files on the disk with a wrong size or sha1
+    pub corrupted: Vec<String>,
+    // files in the libraries / assets directories that no installed version asks for
+    pub extra: Vec<String>,
+    // missing or corrupted files that couldn't be downloaded again
+    pub failed: Vec<FailedDownload>,
+}
+
+impl RepairReport {
+    // nothing is broken, and nothing failed to be downloaded again
+    pub fn is_healthy(&self) -> bool {
+        self.missing.is_empty() && self.corrupted.is_empty() && self.failed.is_empty()
+    }
+}
+
+enum FileState {
+    Valid,
+    Missing,
+    Corrupted,
+}
+
+impl Updater {
+    // walk the libraries, assets, client.jar, asset index and java runtime, listing the broken files
+    // when redownload is true, only the missing / corrupted files are downloaded again
+    pub async fn repair(&mut self, redownload: bool) -> Result<RepairReport> {
+        if self.libs_manifest().is_none() || self.assets_manifest().is_none() {
+            self.update_files_list().await?;
+        }
+        let libs_manifest = self.libs_manifest_or_err()?.clone();
+        let assets_manifest = self.assets_manifest_or_err()?;
+        run_blocking({
+            let (local_dir_path, libraries) = (self.local_dir_path().to_string(), libs_manifest.libraries.clone());
+            move || migrate_flat_libraries(&local_dir_path, &libraries)
+        })
+        .await?;
+
+        // a corrupted file can have the right size, so only the sha1 is trusted here
+        let mut download_manager = self.download_manager();
+        download_manager.set_verify_mode(VerifyMode::Full);
+        let phases = [
+            (Phase::Libraries, download_manager.libs_elements(&libs_manifest.libraries)),
+            (Phase::Assets, download_manager.assets_elements(assets_manifest.objects())),
+            (Phase::GameFiles, download_manager.game_files_elements(&libs_manifest)),
+        ];
+
+        let mut report = RepairReport::default();
+        let expected_files: HashSet<PathBuf> = phases
+            .iter()
+            .flat_map(|(_phase, elements)| elements.iter().map(|element| PathBuf::from(&element.path)))
+            .collect();
+
+        let mut broken_phases = vec![];
+        for (phase, elements) in phases {
+            let mut broken = vec![];
+            for (element, state) in check_elements(elements, download_manager.max_concurrent()).await {
+                match state {
+                    FileState::Valid => continue,
+                    FileState::Missing => report.missing.push(element.path.clone()),
+                    FileState::Corrupted => report.corrupted.push(element.path.clone()),
+                }
+                broken.push(element);
+            }
+            broken_phases.push((phase, broken));
+        }
+
+        let java_path = get_java_path(
+            self.local_dir_path(),
+            &libs_manifest.java_version.major_version.to_string(),
+        );
+        let is_java_missing = !Path::new(&java_path).exists();
+        if is_java_missing {
+            report.missing.push(java_path.clone());
+        }
+
+        report.extra = run_blocking({
+            let (versions_manager, local_dir_path) = (self.download_manager(), self.local_dir_path().to_string());
+            move || Ok(extra_files(&versions_manager, &local_dir_path, expected_files))
+        })
+        .await?;
+
+        if redownload {
+            for (phase, broken) in broken_phases {
+                if !broken.is_empty() {
+                    download_manager.download_phase(phase, broken).await;
+                }
+            }
+            let mut java_error = None;
+            if is_java_missing {
+                java_error = download_manager
+                    .download_java(libs_manifest.java_version.major_version.to_string())
+                    .await
+                    .err();
+            }
+            report.failed = download_manager.download_fails().await;
+            // the url of the runtime isn't known when its lookup failed, the fail gives the missing executable
+            if let Some(error) = java_error {
+                report.failed.push(FailedDownload {
+                    element: DownloadElement {
+                        path: java_path,
+                        ..DownloadElement::default()
+                    },
+                    error,
+                });
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+// files in the libraries / assets directories that no installed version asks for
+fn extra_files(download_manager: &DownloadManager, local_dir_path: &str, mut expected_files: HashSet<PathBuf>) -> Vec<String> {
+    expected_files.extend(installed_versions_files(download_manager, local_dir_path));
+
+    let mut extra = vec![];
+    for directory in [
+        Directory::Libraries.as_str(),
+        Directory::Assets.as_str() + "objects",
+        Directory::Indexes.as_str(),
+    ] {
+        for file in list_files_recursive(Path::new(&(local_dir_path.to_string() + &directory))) {
+            if !expected_files.contains(&file) {
+                extra.push(file.to_string_lossy().to_string());
+            }
+        }
+    }
+    extra
+}
+
+// files of all the versions installed in the same dir, they share the libraries and the assets
+fn installed_versions_files(download_manager: &DownloadManager, local_dir_path: &str) -> HashSet<PathBuf> {
+    let versions_dir = local_dir_path.to_string() + &Directory::Versions.as_str();
+    let mut files = HashSet::new();
+
+    for entry in fs::read_dir(versions_dir).into_iter().flatten().flatten() {
+        let version = entry.file_name().to_string_lossy().to_string();
+        let json_path = get_version_json_path(local_dir_path, &version);
+        let libs_manifest = match fs::read_to_string(json_path)
+            .ok()
+            .and_then(|json| LibsRoot::parse_json(json).ok())
+        {
+            Some(libs_manifest) => libs_manifest,
+            None => continue,
+        };
+
+        let mut elements = download_manager.libs_elements(&libs_manifest.libraries);
+        elements.extend(download_manager.game_files_elements(&libs_manifest));
+        // the objects of the version are listed in its asset index, when it's installed
+        let index_path = get_asset_index_path(local_dir_path, &libs_manifest.asset_index.url);
+        if let Some(assets_manifest) = fs::read_to_string(index_path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<AssetsRoot>(&json).ok())
+        {
+            elements.extend(download_manager.assets_elements(assets_manifest.objects()));
+        }
+        files.extend(elements.into_iter().map(|element| PathBuf::from(element.path)));
+    }
+
+    files
+}
+
+// check the size and sha1 of the files of the elements, max_concurrent at a time
+async fn check_elements(elements: Vec<DownloadElement>, max_concurrent: usize) -> Vec<(DownloadElement, FileState)> {
+    futures::stream::iter(elements)
+        .map(|element| async move {
+            let state = tokio::task::spawn_blocking({
+                let element = element.clone();
+                move || check_element(&element)
+            })
+            .await
+            .unwrap_or(FileState::Corrupted);
+            (element, state)
+        })
+        .buffer_unordered(max_concurrent.max(1))
+        .collect()
+        .await
+}
+
+fn check_element(element: &DownloadElement) -> FileState {
+    let metadata = match std::fs::metadata(&element.path) {
+        Ok(metadata) => metadata,
+        Err(_e) => return FileState::Missing,
+    };
+    if element.size.is_some_and(|size| size != metadata.len()) {
+        return FileState::Corrupted;
+    }
+    match &element.hash {
+        Some(hash) if !check_file_hash_with(&element.path, hash, element.hash_algorithm) => FileState::Corrupted,
+        _ => FileState::Valid,
+    }
+}
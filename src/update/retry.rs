@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+// how the failed downloads are retried by the DownloadManager
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    // total number of tries of a file, the first download included
+    pub max_attempts: u8,
+    // delay before the first retry, doubled at each new retry
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    // no retry at all, the files failing on the first try are reported directly
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    // exponential delay before the given retry (starting at 1), with a random jitter between 50% and 100%
+    pub fn delay(&self, retry: u8) -> Duration {
+        let exponent = u32::from(retry.saturating_sub(1)).min(16);
+        let delay = self
+            .base_delay
+            .saturating_mul(2u32.pow(exponent))
+            .min(self.max_delay);
+
+        (delay / 2).saturating_add((delay / 2).mul_f64(random_jitter()))
+    }
+
+    // can a file that has already been tried `attempts` times be tried again
+    pub fn can_retry(&self, attempts: u8) -> bool {
+        attempts < self.max_attempts
+    }
+}
+
+// random number in [0, 1), 1 when the os gives no random bytes (the whole delay is then waited)
+// it's never above 1, so the half of the delay it multiplies can't overflow, even for Duration::MAX
+fn random_jitter() -> f64 {
+    let mut bytes = [0; 4];
+    match getrandom::getrandom(&mut bytes) {
+        Ok(()) => f64::from(u32::from_le_bytes(bytes)) / (f64::from(u32::MAX) + 1.0),
+        Err(_e) => 1.0,
+    }
+}
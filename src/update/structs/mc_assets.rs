@@ -21,10 +21,22 @@ impl Object {
 pub struct AssetsRoot {
     //               path  ; (hash + size)
     objects: HashMap<String, Object>,
+    // 1.6 to 1.7.2 (index "legacy") : the game reads the assets by name in assets/virtual/<index>/
+    #[serde(default, rename = "virtual")]
+    is_virtual: bool,
+    // before 1.6 (index "pre-1.6") : the game reads the assets by name in <game dir>/resources/
+    #[serde(default)]
+    map_to_resources: bool,
 }
 
 impl AssetsRoot {
     pub fn objects(&self) -> &HashMap<String, Object> {
         &self.objects
     }
+    pub fn is_virtual(&self) -> bool {
+        self.is_virtual
+    }
+    pub fn map_to_resources(&self) -> bool {
+        self.map_to_resources
+    }
 }
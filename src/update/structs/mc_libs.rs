@@ -1,36 +1,40 @@
-use std::{clone, fs};
+use std::collections::HashMap;
+
 use serde_derive::{Deserialize, Serialize};
-use serde_json::Value;
 
-// base of the json from the MC api to get the libs
-#[derive(Clone, Debug)]
+use crate::error::{LauncherError, Result};
+use crate::update::structs::mojang_arch_bits;
+
+// base of the json from the MC api that describes a version (libs, arguments, main class...)
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct LibsRoot {
+    pub id: String,
+    #[serde(default)]
+    pub r#type: String,
+    #[serde(default)]
+    pub release_time: String,
+    #[serde(default)]
+    pub time: String,
+    pub main_class: String,
     pub asset_index: AssetIndex,
-    pub java_version: u8,
+    #[serde(default)]
+    pub assets: String,
+    #[serde(default)]
+    pub java_version: JavaVersion,
+    #[serde(default)]
     pub libraries: Vec<Library>,
-    pub client: Client,
+    pub downloads: Downloads,
+    // arguments of the versions since 1.13
+    pub arguments: Option<Arguments>,
+    // arguments of the versions before 1.13, as a single line
+    pub minecraft_arguments: Option<String>,
+    pub logging: Option<Logging>,
 }
 
 impl LibsRoot {
-    pub fn parse_json(json: String) -> Result<LibsRoot, String> {
-        let json_object: Value = serde_json::from_str(json.as_str()).unwrap();
-
-        let asset_index: AssetIndex =
-            serde_json::from_value(json_object["assetIndex"].clone()).unwrap();
-        let java_version: u8 =
-            serde_json::from_value(json_object["javaVersion"]["majorVersion"].clone()).unwrap();
-        
-        let client: Client = serde_json::from_value(json_object["downloads"]["client"].clone()).unwrap();
-        
-        let 
-
-
-        Ok(LibsRoot {
-            asset_index,
-            java_version,
-            libraries: libraries.libraries,
-            client,
-        })
+    pub fn parse_json(json: String) -> Result<LibsRoot> {
+        serde_json::from_str(json.as_str()).map_err(|e| LauncherError::manifest("version", e))
     }
 }
 
@@ -44,12 +48,223 @@ pub struct AssetIndex {
     pub url: String,
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JavaVersion {
+    pub component: String,
+    pub major_version: u8,
+}
 
+// the oldest versions of the json don't give the java version, they run on java 8
+impl Default for JavaVersion {
+    fn default() -> Self {
+        Self {
+            component: "jre-legacy".to_string(),
+            major_version: 8,
+        }
+    }
+}
 
+// a file of the game to download (client.jar, server.jar, log config...)
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct Client {
+pub struct Download {
+    pub sha1: String,
+    pub size: i64,
+    pub url: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Downloads {
+    pub client: Download,
+    pub server: Option<Download>,
+    pub client_mappings: Option<Download>,
+    pub server_mappings: Option<Download>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Library {
+    // maven coordinates of the lib (group:artifact:version[:classifier])
+    pub name: String,
+    #[serde(default)]
+    pub downloads: LibraryDownloads,
+    // maven repository of the libs that don't have a downloads section
+    pub url: Option<String>,
+    // os name -> classifier of the natives of the lib (with ${arch} to replace)
+    pub natives: Option<HashMap<String, String>>,
+    pub extract: Option<Extract>,
+    pub rules: Option<Vec<Rule>>,
+}
+
+impl Library {
+    // classifier of the natives of the lib for the given os, if the lib has natives for it
+    pub fn native_classifier(&self, os_name: &str) -> Option<String> {
+        self.natives
+            .as_ref()?
+            .get(os_name)
+            .map(|classifier| classifier.replace("${arch}", mojang_arch_bits()))
+    }
+
+    // group:artifact[:classifier] of the lib, the same lib in another version has the same key
+    pub fn maven_key(&self) -> String {
+        let coordinates = self.name.split('@').next().unwrap_or(&self.name);
+        let parts: Vec<&str> = coordinates.split(':').collect();
+        match parts.as_slice() {
+            [group, artifact, _version] => format!("{}:{}", group, artifact),
+            [group, artifact, _version, classifier, ..] => format!("{}:{}:{}", group, artifact, classifier),
+            _ => self.name.clone(),
+        }
+    }
+
+    // jar of the lib, with its path in the libraries dir
+    // the libs only hosted on a maven repository (url + name) don't have a downloads section, nor a hash
+    pub fn main_artifact(&self) -> Option<Artifact> {
+        match &self.downloads.artifact {
+            Some(artifact) => {
+                let mut artifact = artifact.clone();
+                if artifact.path.is_empty() {
+                    artifact.path = maven_path(&self.name)?;
+                }
+                Some(artifact)
+            }
+            None => {
+                let repository = self.url.as_ref()?;
+                let path = maven_path(&self.name)?;
+                Some(Artifact {
+                    url: format!("{}/{}", repository.trim_end_matches('/'), path),
+                    path,
+                    sha1: String::new(),
+                    size: 0,
+                })
+            }
+        }
+    }
+
+    // jar containing the natives of the lib for the given os
+    pub fn native_artifact(&self, os_name: &str) -> Option<Artifact> {
+        let classifier = self.native_classifier(os_name)?;
+        let mut artifact = self.downloads.classifiers.as_ref()?.get(&classifier)?.clone();
+        if artifact.path.is_empty() {
+            artifact.path = maven_path(&format!("{}:{}", self.name, classifier))?;
+        }
+        Some(artifact)
+    }
+}
+
+// path of a jar in a maven repository, from its coordinates (group:artifact:version[:classifier][@extension])
+pub fn maven_path(name: &str) -> Option<String> {
+    let (coordinates, extension) = name.split_once('@').unwrap_or((name, "jar"));
+    let parts: Vec<&str> = coordinates.split(':').collect();
+    let (group, artifact, version, classifier) = match parts.as_slice() {
+        [group, artifact, version] => (group, artifact, version, None),
+        [group, artifact, version, classifier] => (group, artifact, version, Some(classifier)),
+        _ => return None,
+    };
+
+    let file_name = match classifier {
+        Some(classifier) => format!("{}-{}-{}.{}", artifact, version, classifier, extension),
+        None => format!("{}-{}.{}", artifact, version, extension),
+    };
+    Some(format!("{}/{}/{}/{}", group.replace('.', "/"), artifact, version, file_name))
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LibraryDownloads {
+    pub artifact: Option<Artifact>,
+    // classifier -> natives jar
+    pub classifiers: Option<HashMap<String, Artifact>>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Artifact {
+    #[serde(default)]
+    pub path: String,
     pub sha1: String,
     pub size: i64,
     pub url: String,
-}
\ No newline at end of file
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Extract {
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleAction {
+    Allow,
+    Disallow,
+}
+
+// condition on the os / features, used by the libs and the arguments
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rule {
+    pub action: RuleAction,
+    pub os: Option<OsRule>,
+    pub features: Option<HashMap<String, bool>>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OsRule {
+    pub name: Option<String>,
+    pub arch: Option<String>,
+    // regex on the version of the os
+    pub version: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Arguments {
+    #[serde(default)]
+    pub game: Vec<Argument>,
+    #[serde(default)]
+    pub jvm: Vec<Argument>,
+}
+
+// an argument is either a plain string, or values only used when the rules match
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Argument {
+    Plain(String),
+    Conditional {
+        rules: Vec<Rule>,
+        value: ArgumentValue,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ArgumentValue {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+impl ArgumentValue {
+    pub fn values(&self) -> Vec<&str> {
+        match self {
+            ArgumentValue::Single(value) => vec![value.as_str()],
+            ArgumentValue::Multiple(values) => values.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Logging {
+    pub client: Option<LoggingConfig>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoggingConfig {
+    // jvm argument, with ${path} to replace by the path of the config file
+    pub argument: String,
+    pub file: LoggingFile,
+    pub r#type: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoggingFile {
+    pub id: String,
+    pub sha1: String,
+    pub size: i64,
+    pub url: String,
+}
@@ -1,22 +1,23 @@
 use std::env::consts::OS;
-use serde_json::Value;
 
 pub mod mc_assets;
 pub mod mc_libs;
 pub mod mc_versions;
+pub mod rules;
 
-pub fn check_rules(element: Value) -> bool{
-    let rules = serde_json::from_value::<Option<Vec<Value>>>(element).unwrap();
-    if rules.is_none() { return true };
+// name of the current os in the jsons of Mojang
+pub fn mojang_os_name() -> &'static str {
+    match OS {
+        "macos" => "osx",
+        other => other,
+    }
+}
 
-    for rule in rules.unwrap() {
-        let action = rule.get("action");
-        let os = rule.get("os");
-        if action.is_some() && os.is_some() && action.unwrap() == "allow" && os.unwrap() == OS {
-            return true
-        }
+// value of ${arch} in the natives classifiers
+pub fn mojang_arch_bits() -> &'static str {
+    if cfg!(target_pointer_width = "64") {
+        "64"
+    } else {
+        "32"
     }
-    
-    false
-    
-}
\ No newline at end of file
+}
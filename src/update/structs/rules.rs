@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use std::env::consts::ARCH;
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use crate::update::structs::mc_libs::{Argument, Library, OsRule, Rule, RuleAction};
+use crate::update::structs::mojang_os_name;
+
+// features known by the rules of the arguments in the version jsons
+pub const FEATURE_DEMO_USER: &str = "is_demo_user";
+pub const FEATURE_CUSTOM_RESOLUTION: &str = "has_custom_resolution";
+pub const FEATURE_QUICK_PLAY_SUPPORT: &str = "has_quick_plays_support";
+pub const FEATURE_QUICK_PLAY_SINGLEPLAYER: &str = "is_quick_play_singleplayer";
+pub const FEATURE_QUICK_PLAY_MULTIPLAYER: &str = "is_quick_play_multiplayer";
+pub const FEATURE_QUICK_PLAY_REALMS: &str = "is_quick_play_realms";
+
+// what the rules are evaluated against : the os the game runs on and the enabled features
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Environment {
+    // name of the os, as written in the jsons (windows, osx, linux)
+    pub os_name: String,
+    // architecture of the os, as written in the jsons (x86, x86_64, arm64...)
+    pub os_arch: String,
+    pub os_version: String,
+    // a feature missing from the map is disabled
+    pub features: HashMap<String, bool>,
+}
+
+impl Environment {
+    // environment of the current machine, without any feature enabled
+    pub fn current() -> Self {
+        Self {
+            os_name: mojang_os_name().to_string(),
+            os_arch: mojang_arch().to_string(),
+            os_version: current_os_version().to_string(),
+            features: HashMap::new(),
+        }
+    }
+
+    pub fn set_feature(&mut self, feature: &str, enabled: bool) {
+        self.features.insert(feature.to_string(), enabled);
+    }
+
+    pub fn has_feature(&self, feature: &str) -> bool {
+        self.features.get(feature).copied().unwrap_or(false)
+    }
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self::current()
+    }
+}
+
+// getting the version of the os can run external commands, so it's only done once
+fn current_os_version() -> &'static str {
+    static OS_VERSION: OnceLock<String> = OnceLock::new();
+    OS_VERSION.get_or_init(|| os_info::get().version().to_string())
+}
+
+// name of the current architecture in the jsons of Mojang
+fn mojang_arch() -> &'static str {
+    match ARCH {
+        "aarch64" => "arm64",
+        other => other,
+    }
+}
+
+// the rules are applied in order, the action of the last matching rule wins
+// no rules at all means allowed, rules with none matching means disallowed
+pub fn rules_allow(rules: &[Rule], environment: &Environment) -> bool {
+    if rules.is_empty() {
+        return true;
+    }
+
+    rules
+        .iter()
+        .rev()
+        .find(|rule| rule.matches(environment))
+        .is_some_and(|rule| rule.action == RuleAction::Allow)
+}
+
+impl Rule {
+    // tells if every condition of the rule is true in the environment
+    pub fn matches(&self, environment: &Environment) -> bool {
+        let os_matches = self.os.as_ref().is_none_or(|os| os.matches(environment));
+        let features_match = self.features.as_ref().is_none_or(|features| {
+            features
+                .iter()
+                .all(|(feature, enabled)| environment.has_feature(feature) == *enabled)
+        });
+        os_matches && features_match
+    }
+}
+
+impl OsRule {
+    pub fn matches(&self, environment: &Environment) -> bool {
+        let name_matches = self.name.as_ref().is_none_or(|name| *name == environment.os_name);
+        let arch_matches = self.arch.as_ref().is_none_or(|arch| *arch == environment.os_arch);
+        // an invalid regex never matches, like in the official launcher
+        let version_matches = self.version.as_ref().is_none_or(|version| {
+            Regex::new(version).is_ok_and(|regex| regex.is_match(&environment.os_version))
+        });
+        name_matches && arch_matches && version_matches
+    }
+}
+
+impl Library {
+    // tells if the lib is used in the environment
+    pub fn is_allowed(&self, environment: &Environment) -> bool {
+        self.rules
+            .as_ref()
+            .is_none_or(|rules| rules_allow(rules, environment))
+    }
+}
+
+impl Argument {
+    // values of the argument in the environment, empty if its rules don't allow it
+    pub fn values(&self, environment: &Environment) -> Vec<&str> {
+        match self {
+            Argument::Plain(value) => vec![value.as_str()],
+            Argument::Conditional { rules, value } => {
+                if rules_allow(rules, environment) {
+                    value.values()
+                } else {
+                    Vec::new()
+                }
+            }
+        }
+    }
+}
+
+// values of all the arguments allowed in the environment, in order
+pub fn allowed_arguments<'a>(arguments: &'a [Argument], environment: &Environment) -> Vec<&'a str> {
+    arguments
+        .iter()
+        .flat_map(|argument| argument.values(environment))
+        .collect()
+}
@@ -1,80 +1,97 @@
-use crate::update::downloads::DownloadManager;
+
+use reqwest::Client;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::update::downloads::{DownloadManager, InstallReport, VerifyMode, DEFAULT_MAX_CONCURRENT, DEFAULT_MAX_PER_HOST};
+use crate::update::progress::ProgressEvent;
+use crate::update::retry::RetryPolicy;
 use crate::update::structs::mc_assets::AssetsRoot;
 use crate::update::structs::mc_libs::LibsRoot;
-use crate::update::utils::{check_file_hash, get_asset_path_from_hash, get_lib_path_from_url, get_relative_local_dir_path};
+use crate::update::structs::rules::Environment;
+use crate::error::{LauncherError, Result};
+use crate::update::utils::{get_relative_local_dir_path, migrate_flat_libraries, run_blocking};
 
 // struct that manage the update process, contains the manifests and the version of the game
 pub struct Updater {
+    client: Client,
     local_dir_path: String,
     version: String,
     libs_manifest: Option<LibsRoot>,
+    // text of the version json as downloaded, saved as is next to the jar
+    version_json: Option<String>,
     assets_manifest: Option<AssetsRoot>,
+    progress_sender: Option<UnboundedSender<ProgressEvent>>,
+    max_concurrent_downloads: usize,
+    max_downloads_per_host: usize,
+    retry_policy: RetryPolicy,
+    verify_mode: VerifyMode,
+    environment: Environment,
 }
 
 // check files before adding them to the queue
 impl Updater {
     // function that will call the DownloadManager functions, then download the failed files
-    pub fn install_files(&mut self) {
-        println!("---- Installing files -----");
-
-        let mut download_manager = DownloadManager::new(self.local_dir_path.clone());
+    // the files that couldn't be downloaded even after the retries are listed in the report
+    pub async fn install_files(&mut self) -> Result<InstallReport> {
+        let mut download_manager = self.download_manager();
         // download assets + libs + java + game files
-        if let Ok(_result) = &self.update_files_list() {
-            let runtime = tokio::runtime::Runtime::new();
-
-            runtime.unwrap().block_on(async {
-                download_manager
-                    .download_libs(self.libs_manifest.clone().unwrap().libraries)
-                    .await;
-                download_manager
-                    .download_assets(self.assets_manifest.clone().unwrap().objects())
-                    .await;
-                download_manager
-                    .download_java(self.libs_manifest.clone().unwrap().java_version.to_string())
-                    .await;
-                download_manager
-                    .download_game_files(self.libs_manifest.clone().unwrap())
-                    .await;
-            });
-
-            
-            // fails -> downloads direct
-            let runtime = tokio::runtime::Runtime::new();
-            println!("{}", download_manager.fails().len());
-            runtime.unwrap().block_on(async {
-                download_manager.download_fails().await;
-            });
-
-            self.validate_files();
-        }
-        println!("---- End installing files -----");
-    }
-
-    // checking of the hashes to validate the correct installation of all the files needed
-    pub fn validate_files(&self) {
-        // libs
-        for library in self.libs_manifest.clone().unwrap().libraries {
-            if !check_file_hash(
-                get_lib_path_from_url(
-                    self.local_dir_path.clone(),
-                    library.downloads.artifact.url.as_str(),
-                )
-                    .as_str(),
-                library.downloads.artifact.sha1.as_str(),
-            ) {
-                println!("fails libs");
-            };
-        }
+        self.update_files_list().await?;
+        let libs_manifest = self.libs_manifest_or_err()?.clone();
+        let assets_manifest = self.assets_manifest_or_err()?;
+        run_blocking({
+            let (local_dir_path, libraries) = (self.local_dir_path.clone(), libs_manifest.libraries.clone());
+            move || migrate_flat_libraries(&local_dir_path, &libraries)
+        })
+        .await?;
+        self.save_version_json().await?;
+
+        download_manager
+            .download_libs(libs_manifest.libraries.clone())
+            .await;
+        download_manager
+            .download_assets(assets_manifest.objects())
+            .await;
+        download_manager
+            .download_java(libs_manifest.java_version.major_version.to_string())
+            .await?;
+        download_manager
+            .download_game_files(libs_manifest)
+            .await;
+
+        // fails -> downloads direct
+        // every downloaded file is checked against its hash, and the existing ones according to the verify mode
+        Ok(InstallReport {
+            failed: download_manager.download_fails().await,
+        })
+    }
 
-        // assets
-        for asset in self.assets_manifest.clone().unwrap().objects() {
-            if !check_file_hash(
-                get_asset_path_from_hash(self.local_dir_path.clone(), asset.1.hash()).1.as_str(),
-                asset.1.hash(),
-            ) {
-                println!("fails assets")
-            };
-        }
+    // a DownloadManager using the settings of the updater
+    pub(crate) fn download_manager(&self) -> DownloadManager {
+        let mut download_manager =
+            DownloadManager::with_client(self.local_dir_path.clone(), self.client.clone());
+        download_manager.set_progress_sender(self.progress_sender.clone());
+        download_manager.set_max_concurrent(self.max_concurrent_downloads);
+        download_manager.set_max_per_host(self.max_downloads_per_host);
+        download_manager.set_retry_policy(self.retry_policy.clone());
+        download_manager.set_verify_mode(self.verify_mode);
+        download_manager.set_environment(self.environment.clone());
+        download_manager
+    }
+
+    pub(crate) fn libs_manifest_or_err(&self) -> Result<&LibsRoot> {
+        self.libs_manifest
+            .as_ref()
+            .ok_or_else(|| LauncherError::VersionNotFound(self.version.clone()))
+    }
+
+    pub(crate) fn assets_manifest_or_err(&self) -> Result<&AssetsRoot> {
+        self.assets_manifest
+            .as_ref()
+            .ok_or_else(|| LauncherError::VersionNotFound(self.version.clone()))
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
     }
 
     pub fn local_dir_path(&self) -> &str {
@@ -85,14 +102,56 @@ impl Updater {
         &self.version
     }
 
+    pub fn environment(&self) -> &Environment {
+        &self.environment
+    }
+
     pub fn libs_manifest(&self) -> &Option<LibsRoot> {
         &self.libs_manifest
     }
 
+    pub(crate) fn version_json(&self) -> Option<&str> {
+        self.version_json.as_deref()
+    }
+
     pub fn assets_manifest(&self) -> &Option<AssetsRoot> {
         &self.assets_manifest
     }
 
+    // use the given http client (and its configuration) for all the requests of the updater
+    pub fn set_client(&mut self, client: Client) {
+        self.client = client;
+    }
+
+    // send the events of install_files to the given channel (see update::progress::progress_channel)
+    pub fn set_progress_sender(&mut self, progress_sender: UnboundedSender<ProgressEvent>) {
+        self.progress_sender = Some(progress_sender);
+    }
+
+    // number of files downloaded at the same time by install_files
+    pub fn set_max_concurrent_downloads(&mut self, max_concurrent_downloads: usize) {
+        self.max_concurrent_downloads = max_concurrent_downloads;
+    }
+
+    // number of files downloaded at the same time from a single host by install_files
+    pub fn set_max_downloads_per_host(&mut self, max_downloads_per_host: usize) {
+        self.max_downloads_per_host = max_downloads_per_host;
+    }
+
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
+
+    // how install_files checks the files already installed (full sha1 check by default)
+    pub fn set_verify_mode(&mut self, verify_mode: VerifyMode) {
+        self.verify_mode = verify_mode;
+    }
+
+    // os and features the rules of the libs are evaluated against (the current machine by default)
+    pub fn set_environment(&mut self, environment: Environment) {
+        self.environment = environment;
+    }
+
     pub fn set_local_dir_path(&mut self, local_dir_path: String) {
         self.local_dir_path = local_dir_path;
     }
@@ -103,6 +162,14 @@ impl Updater {
 
     pub fn set_libs_manifest(&mut self, libs_manifest: Option<LibsRoot>) {
         self.libs_manifest = libs_manifest;
+        self.version_json = None;
+    }
+
+    // parse the text of the version json, and keep it to save it unchanged
+    pub(crate) fn set_version_json(&mut self, json: String) -> Result<()> {
+        self.libs_manifest = Some(LibsRoot::parse_json(json.clone())?);
+        self.version_json = Some(json);
+        Ok(())
     }
 
     pub fn set_assets_manifest(&mut self, assets_manifest: Option<AssetsRoot>) {
@@ -118,10 +185,19 @@ impl Updater {
 
     pub fn new<V: ToString>(version: V) -> Self {
         Self {
+            client: Client::new(),
             local_dir_path: "".to_string(),
             version: version.to_string(),
             libs_manifest: None,
+            version_json: None,
             assets_manifest: None,
+            progress_sender: None,
+            max_concurrent_downloads: DEFAULT_MAX_CONCURRENT,
+            max_downloads_per_host: DEFAULT_MAX_PER_HOST,
+            retry_policy: RetryPolicy::default(),
+            verify_mode: VerifyMode::default(),
+            environment: Environment::current(),
         }
     }
 }
+
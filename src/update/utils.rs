@@ -1,7 +1,15 @@
-use std::{fs, io};
+use std::fs;
+use std::io::Read;
+use std::path::Path;
 
 use sha2::Digest;
 
+use crate::error::{LauncherError, Result};
+use crate::update::structs::mc_libs::Library;
+
+// dir of the libs before they were stored in the maven layout, every jar was directly in it
+const LEGACY_LIBRARIES_DIR: &str = "libs";
+
 // ----------------------------------------- //
 // Utils files that contains utils functions //
 // ----------------------------------------- //
@@ -21,13 +29,118 @@ pub fn get_relative_local_dir_path(local_dir_path: &str) -> String {
 }
 
 pub(crate) fn get_file_name_from_url(url: &str) -> String {
-    url.split('/').last().unwrap().to_string()
+    url.rsplit('/').next().unwrap_or(url).to_string()
 }
 
-pub(crate) fn get_lib_path_from_url(local_dir_path: String, url: &str) -> String {
+// path of a lib in the libraries dir, from its path in the maven layout (group/artifact/version/file)
+pub(crate) fn get_lib_path(local_dir_path: &str, artifact_path: &str) -> String {
     local_dir_path.to_string()
         + &Directory::Libraries.as_str()
-        + get_file_name_from_url(url).as_str()
+        + &artifact_path.replace('/', std::path::MAIN_SEPARATOR_STR)
+}
+
+// move the libs installed in the old flat libs/ dir to their place in the maven layout
+// the old dir is removed once empty, the files that don't belong to these libs are kept in it
+pub(crate) fn migrate_flat_libraries(local_dir_path: &str, libraries: &[Library]) -> Result<()> {
+    let flat_dir = local_dir_path.to_string() + LEGACY_LIBRARIES_DIR + std::path::MAIN_SEPARATOR_STR;
+    if !Path::new(&flat_dir).is_dir() {
+        return Ok(());
+    }
+
+    let artifacts = libraries.iter().flat_map(|library| {
+        library
+            .main_artifact()
+            .into_iter()
+            .chain(library.downloads.classifiers.iter().flat_map(|classifiers| classifiers.values().cloned()))
+    });
+    for artifact in artifacts.filter(|artifact| !artifact.path.is_empty()) {
+        let flat_path = flat_dir.clone() + &get_file_name_from_url(&artifact.path);
+        let lib_path = get_lib_path(local_dir_path, &artifact.path);
+        if !Path::new(&flat_path).is_file() || Path::new(&lib_path).exists() {
+            continue;
+        }
+        if let Some(parent) = Path::new(&lib_path).parent() {
+            fs::create_dir_all(parent).map_err(|e| LauncherError::io(parent.display(), e))?;
+        }
+        fs::rename(&flat_path, &lib_path).map_err(|e| LauncherError::io(&flat_path, e))?;
+    }
+
+    // fails when some files are still in it
+    let _ = fs::remove_dir(&flat_dir);
+    Ok(())
+}
+
+// dir of the java runtime of a major version (runtime/java-<version>/), the versions of the game don't all use the same
+pub(crate) fn get_runtime_path(local_dir_path: &str, java_version: &str) -> String {
+    local_dir_path.to_string()
+        + &Directory::Runtime.as_str()
+        + "java-"
+        + java_version
+        + std::path::MAIN_SEPARATOR_STR
+}
+
+// path of the java executable of the runtime installed by the DownloadManager
+pub(crate) fn get_java_path(local_dir_path: &str, java_version: &str) -> String {
+    let extension = if cfg!(windows) { ".exe" } else { "" };
+    get_runtime_path(local_dir_path, java_version)
+        + "bin"
+        + std::path::MAIN_SEPARATOR_STR
+        + "java"
+        + extension
+}
+
+// dir of a version of the game, with its jar and its json (versions/<id>/)
+pub(crate) fn get_version_dir(local_dir_path: &str, version: &str) -> String {
+    local_dir_path.to_string() + &Directory::Versions.as_str() + version + std::path::MAIN_SEPARATOR_STR
+}
+
+pub(crate) fn get_client_jar_path(local_dir_path: &str, version: &str) -> String {
+    get_version_dir(local_dir_path, version) + version + ".jar"
+}
+
+pub(crate) fn get_version_json_path(local_dir_path: &str, version: &str) -> String {
+    get_version_dir(local_dir_path, version) + version + ".json"
+}
+
+pub(crate) fn get_asset_index_path(local_dir_path: &str, asset_index_url: &str) -> String {
+    local_dir_path.to_string() + &Directory::Indexes.as_str() + &get_file_name_from_url(asset_index_url)
+}
+
+// log4j config of the game, given by the version json (assets/log_configs/<id>)
+pub(crate) fn get_log_config_path(local_dir_path: &str, config_id: &str) -> String {
+    local_dir_path.to_string() + &Directory::LogConfigs.as_str() + config_id
+}
+
+// all the files of a directory and its sub directories (nothing if it doesn't exist)
+pub(crate) fn list_files_recursive(dir: &std::path::Path) -> Vec<std::path::PathBuf> {
+    let mut files = vec![];
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                files.extend(list_files_recursive(&path));
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files
+}
+
+// run the filesystem / zip work of an async function on the blocking threads of tokio, so the runtime isn't stalled
+pub(crate) async fn run_blocking<T, F>(task: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    tokio::task::spawn_blocking(task)
+        .await
+        .map_err(|e| LauncherError::io("blocking task", e.into()))?
+}
+
+// temporary path of a file while it is downloading
+pub(crate) fn get_part_path(path: &str) -> String {
+    path.to_string() + ".part"
 }
 
 pub(crate) fn get_asset_path_from_hash(local_dir_path: String, hash: &str) -> (String, String) {
@@ -53,67 +166,125 @@ pub(crate) enum Directory {
     Assets,
     Indexes,
     Runtime,
+    Natives,
+    Versions,
+    LogConfigs,
 }
 
 impl Directory {
     pub fn as_str(&self) -> String {
         match self {
-            Directory::Libraries => "libs".to_string() + std::path::MAIN_SEPARATOR_STR,
+            Directory::Libraries => "libraries".to_string() + std::path::MAIN_SEPARATOR_STR,
             Directory::Assets => "assets".to_string() + std::path::MAIN_SEPARATOR_STR,
             Directory::Indexes => {
                 Directory::Assets.as_str() + &*"indexes".to_string() + std::path::MAIN_SEPARATOR_STR
             }
             Directory::Runtime => "runtime".to_string() + std::path::MAIN_SEPARATOR_STR,
+            Directory::Natives => "natives".to_string() + std::path::MAIN_SEPARATOR_STR,
+            Directory::Versions => "versions".to_string() + std::path::MAIN_SEPARATOR_STR,
+            Directory::LogConfigs => {
+                Directory::Assets.as_str() + "log_configs" + std::path::MAIN_SEPARATOR_STR
+            }
         }
     }
 }
 
 // creating all the dirs of the file tree
-pub fn check_all_directories(base_dir: String) -> bool {
+pub fn check_all_directories(base_dir: String) -> Result<()> {
     if base_dir.is_empty() {
-        eprintln!("Please set the local directory path before installing files");
-        return false;
+        return Err(LauncherError::io(
+            base_dir,
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "Please set the local directory path before installing files",
+            ),
+        ));
     }
 
-    //check if the local directory exists
-    if !std::path::Path::new(&base_dir).exists() {
-        println!("Path not found, creating directory: {}", &base_dir);
-        fs::create_dir_all(&base_dir).unwrap();
+    //check if the local directory, the lib directory and the runtime directory exists
+    for directory in [
+        base_dir.clone(),
+        base_dir.to_string() + &Directory::Libraries.as_str(),
+        base_dir.to_string() + &Directory::Runtime.as_str(),
+        base_dir.to_string() + &Directory::Versions.as_str(),
+    ] {
+        if !std::path::Path::new(&directory).exists() {
+            fs::create_dir_all(&directory).map_err(|e| LauncherError::io(&directory, e))?;
+        }
     }
 
-    //check if lib directory exists
-    if !std::path::Path::new(&(base_dir.to_string() + &Directory::Libraries.as_str())).exists() {
-        println!(
-            "Path not found, creating directory: {}",
-            base_dir.to_string() + &Directory::Libraries.as_str()
-        );
-        fs::create_dir_all(base_dir.to_string() + &Directory::Libraries.as_str()).unwrap();
-    };
-
-    if !std::path::Path::new(&(base_dir.to_string() + &Directory::Runtime.as_str())).exists() {
-        println!(
-            "Path not found, creating directory: {}",
-            base_dir.to_string() + &Directory::Runtime.as_str()
-        );
-        fs::create_dir_all(base_dir.to_string() + &Directory::Runtime.as_str()).unwrap();
-    };
-
     // NOTE: the assets directories are created in the download_assets function (src/update/downloads.rs)
-    true
+    Ok(())
+}
+
+// algorithm of the hash of a file, the manifests of mojang give sha1 and azul gives sha256
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HashAlgorithm {
+    #[default]
+    Sha1,
+    Sha256,
+}
+
+// hash of a file computed chunk by chunk, with the algorithm of the file
+pub(crate) enum FileHasher {
+    Sha1(sha1::Sha1),
+    Sha256(sha2::Sha256),
+}
+
+impl FileHasher {
+    pub(crate) fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Sha1 => Self::Sha1(sha1::Sha1::new()),
+            HashAlgorithm::Sha256 => Self::Sha256(sha2::Sha256::new()),
+        }
+    }
+
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Sha1(hasher) => hasher.update(data),
+            Self::Sha256(hasher) => hasher.update(data),
+        }
+    }
+
+    // the hash as an hex string
+    pub(crate) fn finalize(self) -> String {
+        match self {
+            Self::Sha1(hasher) => hex::encode(hasher.finalize()),
+            Self::Sha256(hasher) => hex::encode(hasher.finalize()),
+        }
+    }
 }
 
 // check hash of a file
 pub fn check_file_hash(file_path: &str, hash: &str) -> bool {
-    let file = fs::File::open(file_path);
-    if let Ok(mut file) = file {
-        let mut hasher = sha1::Sha1::new();
-        io::copy(&mut file, &mut hasher).unwrap();
-        let file_hash = hasher.finalize();
-        let computed_hash = hex::encode(file_hash);
-        hash == computed_hash
-    } else if let Err(_e) = file {
-        false
-    } else {
-        false
+    check_file_hash_with(file_path, hash, HashAlgorithm::Sha1)
+}
+
+// same as check_file_hash, for a hash that isn't a sha1
+pub fn check_file_hash_with(file_path: &str, hash: &str, algorithm: HashAlgorithm) -> bool {
+    match compute_file_hash_with(file_path, algorithm) {
+        Ok(computed_hash) => hash == computed_hash,
+        Err(_e) => false,
+    }
+}
+
+// compute the sha1 of a file, as an hex string
+pub fn compute_file_hash(file_path: &str) -> Result<String> {
+    compute_file_hash_with(file_path, HashAlgorithm::Sha1)
+}
+
+pub fn compute_file_hash_with(file_path: &str, algorithm: HashAlgorithm) -> Result<String> {
+    let mut file = fs::File::open(file_path).map_err(|e| LauncherError::io(file_path, e))?;
+    let mut hasher = FileHasher::new(algorithm);
+    let mut buffer = [0u8; 8192];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .map_err(|e| LauncherError::io(file_path, e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
     }
-}
\ No newline at end of file
+    Ok(hasher.finalize())
+}